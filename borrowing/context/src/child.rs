@@ -0,0 +1,74 @@
+#![forbid(unsafe_code)]
+
+use std::any::{Any, TypeId};
+
+use crate::{Context, ContextError};
+
+pub struct ChildContext<'a> {
+    parent: &'a Context,
+    local: Context,
+}
+
+impl Context {
+    pub fn child(&self) -> ChildContext<'_> {
+        ChildContext {
+            parent: self,
+            local: Context::new(),
+        }
+    }
+}
+
+impl<'a> ChildContext<'a> {
+    pub fn parent(&self) -> &'a Context {
+        self.parent
+    }
+
+    pub fn insert<K: ToString, Q: Any>(&mut self, key: K, obj: Q) {
+        self.local.insert(key, obj);
+    }
+
+    pub fn get<Q: Any>(&self, key: &str) -> &Q {
+        self.try_get(key).unwrap_or_else(|err| panic!("{err}"))
+    }
+
+    pub fn try_get<Q: Any>(&self, key: &str) -> Result<&Q, ContextError> {
+        if self.local.contains(key) {
+            self.local.try_get(key)
+        } else {
+            self.parent.try_get(key)
+        }
+    }
+
+    pub fn get_mut<Q: Any>(&mut self, key: &str) -> Result<&mut Q, ContextError> {
+        self.local.get_mut(key)
+    }
+
+    pub fn contains(&self, key: &str) -> bool {
+        self.local.contains(key) || self.parent.contains(key)
+    }
+
+    pub fn remove(&mut self, key: &str) -> bool {
+        self.local.remove(key)
+    }
+
+    pub fn insert_singletone<Q: Any>(&mut self, obj: Q) {
+        self.local.insert_singletone(obj);
+    }
+
+    pub fn get_singletone<Q: Any>(&self) -> &Q {
+        self.try_get_singletone()
+            .unwrap_or_else(|err| panic!("{err}"))
+    }
+
+    pub fn try_get_singletone<Q: Any>(&self) -> Result<&Q, ContextError> {
+        if self.local.singletone_map.contains_key(&TypeId::of::<Q>()) {
+            self.local.try_get_singletone()
+        } else {
+            self.parent.try_get_singletone()
+        }
+    }
+
+    pub fn take<Q: Any>(&mut self) -> Option<Q> {
+        self.local.take()
+    }
+}
@@ -0,0 +1,300 @@
+#![forbid(unsafe_code)]
+
+use std::{any::Any, collections::HashMap, error::Error, fmt, fs, io, path::Path, str::FromStr};
+
+use crate::{Context, Entry};
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug)]
+pub enum ConfigError {
+    Io(io::Error),
+    Syntax {
+        line: usize,
+        message: String,
+    },
+    Value {
+        key: String,
+        line: usize,
+        message: String,
+    },
+    FeatureDisabled(&'static str),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "failed to read config: {err}"),
+            Self::Syntax { line, message } => write!(f, "line {line}: {message}"),
+            Self::Value { key, line, message } => write!(f, "line {line}: {key}: {message}"),
+            Self::FeatureDisabled(feature) => {
+                write!(
+                    f,
+                    "loading .{feature} files requires the `{feature}` feature"
+                )
+            }
+        }
+    }
+}
+
+impl Error for ConfigError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for ConfigError {
+    fn from(err: io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+type Parser = Box<dyn Fn(&str) -> Result<Entry, String>>;
+type Values = Vec<(String, Result<String, String>, usize)>;
+
+#[derive(Default)]
+pub struct ConfigLoader {
+    parsers: HashMap<String, Parser>,
+}
+
+impl ConfigLoader {
+    pub fn new() -> Self {
+        Self {
+            parsers: HashMap::new(),
+        }
+    }
+
+    pub fn register_parser<Q>(&mut self, key: &str)
+    where
+        Q: Any + FromStr,
+        Q::Err: fmt::Display,
+    {
+        self.register_parser_with(key, |value| Q::from_str(value).map_err(|e| e.to_string()));
+    }
+
+    pub fn register_parser_with<Q, F>(&mut self, key: &str, parser: F)
+    where
+        Q: Any,
+        F: Fn(&str) -> Result<Q, String> + 'static,
+    {
+        let parser: Parser = Box::new(move |value| parser(value).map(Entry::new));
+        self.parsers.insert(key.to_string(), parser);
+    }
+
+    pub fn load_file<P: AsRef<Path>>(&self, cx: &mut Context, path: P) -> Result<(), ConfigError> {
+        let path = path.as_ref();
+        let source = fs::read_to_string(path)?;
+        match path.extension().and_then(|ext| ext.to_str()) {
+            #[cfg(feature = "toml")]
+            Some("toml") => self.load_toml(cx, &source),
+            #[cfg(not(feature = "toml"))]
+            Some("toml") => Err(ConfigError::FeatureDisabled("toml")),
+            #[cfg(feature = "json")]
+            Some("json") => self.load_json(cx, &source),
+            #[cfg(not(feature = "json"))]
+            Some("json") => Err(ConfigError::FeatureDisabled("json")),
+            _ => self.load_env(cx, &source),
+        }
+    }
+
+    pub fn load_env(&self, cx: &mut Context, source: &str) -> Result<(), ConfigError> {
+        let mut values = vec![];
+        for (i, line) in source.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let line = line.strip_prefix("export ").unwrap_or(line);
+            let Some((key, value)) = line.split_once('=') else {
+                return Err(ConfigError::Syntax {
+                    line: i + 1,
+                    message: format!("expected KEY=value, found {line:?}"),
+                });
+            };
+
+            let value = value.trim();
+            let value = ['"', '\'']
+                .iter()
+                .find_map(|q| value.strip_prefix(*q)?.strip_suffix(*q))
+                .unwrap_or(value);
+            values.push((key.trim().to_string(), Ok(value.to_string()), i + 1));
+        }
+
+        self.apply(cx, values)
+    }
+
+    #[cfg(feature = "toml")]
+    pub fn load_toml(&self, cx: &mut Context, source: &str) -> Result<(), ConfigError> {
+        use toml::de::{DeTable, DeValue};
+
+        fn flatten(
+            prefix: &str,
+            table: &DeTable<'_>,
+            source: &str,
+            values: &mut Values,
+        ) -> Result<(), ConfigError> {
+            for (key, value) in table {
+                let key = match prefix {
+                    "" => key.get_ref().to_string(),
+                    _ => format!("{prefix}.{}", key.get_ref()),
+                };
+                let line = line_of(source, value.span().start);
+                let value = match value.get_ref() {
+                    DeValue::Table(table) => {
+                        flatten(&key, table, source, values)?;
+                        continue;
+                    }
+                    DeValue::String(s) => Ok(s.to_string()),
+                    DeValue::Integer(int) => i128::from_str_radix(int.as_str(), int.radix())
+                        .map(|int| int.to_string())
+                        .map_err(|e| e.to_string()),
+                    DeValue::Float(float) => Ok(float.as_str().to_string()),
+                    DeValue::Boolean(b) => Ok(b.to_string()),
+                    DeValue::Datetime(dt) => Ok(dt.to_string()),
+                    DeValue::Array(_) => Err("arrays are not supported".to_string()),
+                };
+                values.push((key, value, line));
+            }
+            Ok(())
+        }
+
+        let table = DeTable::parse(source).map_err(|err| ConfigError::Syntax {
+            line: err.span().map_or(1, |span| line_of(source, span.start)),
+            message: err.message().to_string(),
+        })?;
+
+        let mut values = vec![];
+        flatten("", table.get_ref(), source, &mut values)?;
+        self.apply(cx, values)
+    }
+
+    #[cfg(feature = "json")]
+    pub fn load_json(&self, cx: &mut Context, source: &str) -> Result<(), ConfigError> {
+        use serde_json::Value;
+
+        fn flatten(
+            path: &mut Vec<String>,
+            object: &serde_json::Map<String, Value>,
+            lines: &HashMap<Vec<String>, usize>,
+            values: &mut Values,
+        ) -> Result<(), ConfigError> {
+            for (key, value) in object {
+                path.push(key.clone());
+                let key = path.join(".");
+                let line = lines.get(path.as_slice()).copied().unwrap_or(1);
+                let value = match value {
+                    Value::Object(object) => {
+                        flatten(path, object, lines, values)?;
+                        path.pop();
+                        continue;
+                    }
+                    Value::String(s) => Ok(s.clone()),
+                    Value::Number(n) => Ok(n.to_string()),
+                    Value::Bool(b) => Ok(b.to_string()),
+                    Value::Null | Value::Array(_) => Err(format!("unsupported value {value}")),
+                };
+                path.pop();
+                values.push((key, value, line));
+            }
+            Ok(())
+        }
+
+        let object: serde_json::Map<String, Value> =
+            serde_json::from_str(source).map_err(|err| ConfigError::Syntax {
+                line: err.line(),
+                message: err.to_string(),
+            })?;
+
+        let mut values = vec![];
+        flatten(&mut vec![], &object, &json_key_lines(source), &mut values)?;
+        self.apply(cx, values)
+    }
+
+    fn apply(&self, cx: &mut Context, values: Values) -> Result<(), ConfigError> {
+        let mut entries = vec![];
+        for (key, value, line) in values {
+            let Some(parser) = self.parsers.get(&key) else {
+                continue;
+            };
+            match value.and_then(|value| parser(&value)) {
+                Ok(entry) => entries.push((key, entry)),
+                Err(message) => return Err(ConfigError::Value { key, line, message }),
+            }
+        }
+
+        for (key, entry) in entries {
+            cx.insert_entry(key, entry);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(any(feature = "toml", feature = "json"))]
+fn line_of(source: &str, offset: usize) -> usize {
+    source[..offset.min(source.len())].matches('\n').count() + 1
+}
+
+// Lines of all object keys by their path. Only runs on sources `serde_json`
+// has accepted, so the input is known to be well-formed.
+#[cfg(feature = "json")]
+fn json_key_lines(source: &str) -> HashMap<Vec<String>, usize> {
+    enum Open {
+        Object { has_key: bool },
+        Array,
+    }
+
+    let bytes = source.as_bytes();
+    let mut lines = HashMap::new();
+    let mut path = vec![];
+    let mut stack = vec![];
+    let mut expect_key = false;
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'{' => {
+                stack.push(Open::Object { has_key: false });
+                expect_key = true;
+            }
+            b'[' => stack.push(Open::Array),
+            b',' | b'}' | b']' => {
+                if let Some(Open::Object { has_key }) = stack.last_mut() {
+                    if *has_key {
+                        path.pop();
+                        *has_key = false;
+                    }
+                    expect_key = bytes[i] == b',';
+                }
+                if bytes[i] != b',' {
+                    stack.pop();
+                }
+            }
+            b'"' => {
+                let start = i;
+                i += 1;
+                while bytes[i] != b'"' {
+                    i += if bytes[i] == b'\\' { 2 } else { 1 };
+                }
+                if expect_key {
+                    let key: String = serde_json::from_str(&source[start..=i]).unwrap();
+                    path.push(key);
+                    lines
+                        .entry(path.clone())
+                        .or_insert_with(|| line_of(source, start));
+                    if let Some(Open::Object { has_key }) = stack.last_mut() {
+                        *has_key = true;
+                    }
+                    expect_key = false;
+                }
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    lines
+}
@@ -0,0 +1,58 @@
+#![forbid(unsafe_code)]
+
+use std::{any::Any, fmt};
+
+use crate::{Context, Entry};
+
+impl Context {
+    pub fn insert_debug<K: ToString, Q: Any + fmt::Debug>(&mut self, key: K, obj: Q) {
+        let mut entry = Entry::new(obj);
+        entry.debug = Some(|obj, f| fmt::Debug::fmt(obj.downcast_ref::<Q>().unwrap(), f));
+        self.insert_entry(key.to_string(), entry);
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &str> {
+        self.map.keys().map(String::as_str)
+    }
+
+    pub fn type_name_of(&self, key: &str) -> Option<&'static str> {
+        self.map.get(key).map(|entry| entry.type_name)
+    }
+
+    pub fn singleton_types(&self) -> impl Iterator<Item = &'static str> + '_ {
+        self.singletone_map.values().map(|entry| entry.type_name)
+    }
+}
+
+impl fmt::Debug for Entry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.type_name)?;
+        if let Some(debug) = self.debug {
+            f.write_str(" = ")?;
+            debug(&*self.obj, f)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Debug for Context {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut keys: Vec<_> = self.map.iter().collect();
+        keys.sort_unstable_by_key(|(key, _)| key.as_str());
+        let mut singletons: Vec<_> = self.singleton_types().collect();
+        singletons.sort_unstable();
+
+        f.debug_struct("Context")
+            .field("keys", &DebugMap(keys))
+            .field("singletons", &singletons)
+            .finish()
+    }
+}
+
+struct DebugMap<'a>(Vec<(&'a String, &'a Entry)>);
+
+impl fmt::Debug for DebugMap<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.0.iter().copied()).finish()
+    }
+}
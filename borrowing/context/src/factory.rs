@@ -0,0 +1,120 @@
+#![forbid(unsafe_code)]
+
+use std::{
+    any::{type_name, Any, TypeId},
+    cell::{OnceCell, RefCell},
+};
+
+use crate::{scope::Undo, Context, ContextError, Entry};
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Lifetime {
+    Singleton,
+    Transient,
+}
+
+type Build = Box<dyn Fn(&Context) -> Result<Entry, ContextError>>;
+
+pub(crate) struct Factory {
+    build: Build,
+    lifetime: Lifetime,
+    instance: OnceCell<Entry>,
+}
+
+impl Factory {
+    pub(crate) fn take_instance(&mut self) -> Option<Entry> {
+        self.instance.take()
+    }
+}
+
+#[derive(Default)]
+pub(crate) struct Resolving {
+    stack: RefCell<Vec<(TypeId, &'static str)>>,
+}
+
+struct ResolvingGuard<'a> {
+    resolving: &'a Resolving,
+}
+
+impl Resolving {
+    fn enter<Q: Any>(&self) -> Result<ResolvingGuard<'_>, ContextError> {
+        let mut stack = self.stack.borrow_mut();
+        let id = TypeId::of::<Q>();
+        if let Some(pos) = stack.iter().position(|(other, _)| *other == id) {
+            let mut path: Vec<_> = stack[pos..].iter().map(|(_, name)| *name).collect();
+            path.push(type_name::<Q>());
+            return Err(ContextError::Cycle(path));
+        }
+
+        stack.push((id, type_name::<Q>()));
+        Ok(ResolvingGuard { resolving: self })
+    }
+}
+
+impl Drop for ResolvingGuard<'_> {
+    fn drop(&mut self) {
+        self.resolving.stack.borrow_mut().pop();
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+impl Context {
+    pub fn register_factory<Q, F>(&mut self, factory: F)
+    where
+        Q: Any,
+        F: Fn(&Context) -> Result<Q, ContextError> + 'static,
+    {
+        self.register_factory_with(Lifetime::Singleton, factory);
+    }
+
+    pub fn register_factory_with<Q, F>(&mut self, lifetime: Lifetime, factory: F)
+    where
+        Q: Any,
+        F: Fn(&Context) -> Result<Q, ContextError> + 'static,
+    {
+        let build: Build = Box::new(move |cx| factory(cx).map(Entry::new));
+        let old = self.factories.insert(
+            TypeId::of::<Q>(),
+            Factory {
+                build,
+                lifetime,
+                instance: OnceCell::new(),
+            },
+        );
+        self.journal.record(Undo::Factory(TypeId::of::<Q>(), old));
+    }
+
+    pub fn create<Q: Any>(&self) -> Result<Q, ContextError> {
+        let factory = self
+            .factories
+            .get(&TypeId::of::<Q>())
+            .filter(|factory| factory.lifetime == Lifetime::Transient)
+            .ok_or_else(|| ContextError::MissingKey(type_name::<Q>().to_string()))?;
+
+        let _guard = self.resolving.enter::<Q>()?;
+        let entry = (factory.build)(self)?;
+        Ok(*entry.obj.downcast().unwrap())
+    }
+
+    pub(crate) fn resolve_singletone<Q: Any>(&self) -> Option<Result<&Entry, ContextError>> {
+        let factory = self
+            .factories
+            .get(&TypeId::of::<Q>())
+            .filter(|factory| factory.lifetime == Lifetime::Singleton)?;
+        if let Some(entry) = factory.instance.get() {
+            return Some(Ok(entry));
+        }
+
+        let built = self
+            .resolving
+            .enter::<Q>()
+            .and_then(|_guard| (factory.build)(self));
+        Some(built.map(|entry| {
+            self.journal.record(Undo::Instance(TypeId::of::<Q>()));
+            factory.instance.get_or_init(|| entry)
+        }))
+    }
+}
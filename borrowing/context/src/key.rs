@@ -0,0 +1,63 @@
+#![forbid(unsafe_code)]
+
+use std::{any::Any, fmt, marker::PhantomData};
+
+use crate::{ChildContext, Context};
+
+pub struct TypedKey<Q> {
+    name: &'static str,
+    _type: PhantomData<fn() -> Q>,
+}
+
+impl<Q> TypedKey<Q> {
+    pub const fn new(name: &'static str) -> Self {
+        Self {
+            name,
+            _type: PhantomData,
+        }
+    }
+
+    pub const fn name(&self) -> &'static str {
+        self.name
+    }
+}
+
+impl<Q> Clone for TypedKey<Q> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<Q> Copy for TypedKey<Q> {}
+
+impl<Q> fmt::Debug for TypedKey<Q> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("TypedKey").field(&self.name).finish()
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+impl Context {
+    pub fn insert_typed<Q: Any>(&mut self, key: &TypedKey<Q>, obj: Q) {
+        self.insert(key.name, obj);
+    }
+
+    pub fn get_typed<Q: Any>(&self, key: &TypedKey<Q>) -> Option<&Q> {
+        self.try_get(key.name).ok()
+    }
+
+    pub fn get_typed_mut<Q: Any>(&mut self, key: &TypedKey<Q>) -> Option<&mut Q> {
+        self.get_mut(key.name).ok()
+    }
+}
+
+impl ChildContext<'_> {
+    pub fn insert_typed<Q: Any>(&mut self, key: &TypedKey<Q>, obj: Q) {
+        self.insert(key.name, obj);
+    }
+
+    pub fn get_typed<Q: Any>(&self, key: &TypedKey<Q>) -> Option<&Q> {
+        self.try_get(key.name).ok()
+    }
+}
@@ -1,14 +1,105 @@
 #![forbid(unsafe_code)]
 
 use std::{
-    any::{Any, TypeId},
-    collections::HashMap,
+    any::{type_name, Any, TypeId},
+    collections::{hash_map, HashMap},
+    error::Error,
+    fmt,
 };
 
+mod child;
+mod config;
+mod debug;
+mod factory;
+mod key;
+mod observe;
+mod scope;
+mod sync;
+
+pub use child::ChildContext;
+pub use config::{ConfigError, ConfigLoader};
+pub use factory::Lifetime;
+pub use key::TypedKey;
+pub use observe::Subscription;
+pub use sync::SyncContext;
+
+use factory::{Factory, Resolving};
+use observe::Observers;
+use scope::{Journal, Undo};
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ContextError {
+    MissingKey(String),
+    TypeMismatch {
+        expected: &'static str,
+        actual_type_name: &'static str,
+    },
+    Cycle(Vec<&'static str>),
+}
+
+impl fmt::Display for ContextError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingKey(key) => write!(f, "missing key {key:?}"),
+            Self::TypeMismatch {
+                expected,
+                actual_type_name,
+            } => write!(f, "expected {expected}, found {actual_type_name}"),
+            Self::Cycle(path) => write!(f, "dependency cycle: {}", path.join(" -> ")),
+        }
+    }
+}
+
+impl Error for ContextError {}
+
+////////////////////////////////////////////////////////////////////////////////
+
+type DebugFn = fn(&dyn Any, &mut fmt::Formatter<'_>) -> fmt::Result;
+
+struct Entry {
+    obj: Box<dyn Any>,
+    type_name: &'static str,
+    debug: Option<DebugFn>,
+}
+
+impl Entry {
+    fn new<Q: Any>(obj: Q) -> Self {
+        Self {
+            obj: Box::new(obj),
+            type_name: type_name::<Q>(),
+            debug: None,
+        }
+    }
+
+    fn downcast_ref<Q: Any>(&self) -> Result<&Q, ContextError> {
+        self.obj.downcast_ref().ok_or_else(|| self.mismatch::<Q>())
+    }
+
+    fn downcast_mut<Q: Any>(&mut self) -> Result<&mut Q, ContextError> {
+        let err = self.mismatch::<Q>();
+        self.obj.downcast_mut().ok_or(err)
+    }
+
+    fn mismatch<Q: Any>(&self) -> ContextError {
+        ContextError::TypeMismatch {
+            expected: type_name::<Q>(),
+            actual_type_name: self.type_name,
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
 #[derive(Default)]
 pub struct Context {
-    map: HashMap<String, Box<dyn Any>>,
-    singletone_map: HashMap<TypeId, Box<dyn Any>>,
+    map: HashMap<String, Entry>,
+    singletone_map: HashMap<TypeId, Entry>,
+    factories: HashMap<TypeId, Factory>,
+    resolving: Resolving,
+    observers: Observers,
+    journal: Journal,
 }
 
 impl Context {
@@ -16,26 +107,128 @@ impl Context {
         Self {
             map: HashMap::new(),
             singletone_map: HashMap::new(),
+            factories: HashMap::new(),
+            resolving: Resolving::default(),
+            observers: Observers::default(),
+            journal: Journal::default(),
         }
     }
 
     pub fn insert<K: ToString, Q: Any>(&mut self, key: K, obj: Q) {
-        self.map.insert(key.to_string(), Box::new(obj));
+        self.insert_entry(key.to_string(), Entry::new(obj));
+    }
+
+    fn insert_entry(&mut self, key: String, entry: Entry) {
+        let journal_key = self.journal.is_active().then(|| key.clone());
+        let old = self.replace_entry(key, entry);
+        if let Some(key) = journal_key {
+            self.journal.record(Undo::Key(key, old));
+        }
+    }
+
+    fn replace_entry(&mut self, key: String, entry: Entry) -> Option<Entry> {
+        match self.map.entry(key) {
+            hash_map::Entry::Occupied(mut slot) => {
+                let old = slot.insert(entry);
+                self.observers
+                    .notify_key(slot.key(), &*old.obj, &*slot.get().obj);
+                Some(old)
+            }
+            hash_map::Entry::Vacant(slot) => {
+                slot.insert(entry);
+                None
+            }
+        }
     }
 
     pub fn get<Q: Any>(&self, key: &str) -> &Q {
-        self.map.get(key).unwrap().downcast_ref().unwrap()
+        self.try_get(key).unwrap_or_else(|err| panic!("{err}"))
+    }
+
+    pub fn try_get<Q: Any>(&self, key: &str) -> Result<&Q, ContextError> {
+        self.map
+            .get(key)
+            .ok_or_else(|| ContextError::MissingKey(key.to_string()))?
+            .downcast_ref()
+    }
+
+    pub fn get_mut<Q: Any>(&mut self, key: &str) -> Result<&mut Q, ContextError> {
+        self.map
+            .get_mut(key)
+            .ok_or_else(|| ContextError::MissingKey(key.to_string()))?
+            .downcast_mut()
+    }
+
+    pub fn contains(&self, key: &str) -> bool {
+        self.map.contains_key(key)
+    }
+
+    pub fn remove(&mut self, key: &str) -> bool {
+        match self.map.remove(key) {
+            Some(old) => {
+                self.journal.record(Undo::Key(key.to_string(), Some(old)));
+                true
+            }
+            None => false,
+        }
     }
 
     pub fn insert_singletone<Q: Any>(&mut self, obj: Q) {
-        self.singletone_map.insert(obj.type_id(), Box::new(obj));
+        let id = TypeId::of::<Q>();
+        let old = self.replace_singletone(id, Entry::new(obj));
+        self.journal.record(Undo::Singletone(id, old));
+    }
+
+    fn replace_singletone(&mut self, id: TypeId, entry: Entry) -> Option<Entry> {
+        let old = self.singletone_map.insert(id, entry);
+        if let Some(old) = &old {
+            let new = &self.singletone_map[&id];
+            self.observers.notify_singletone(id, &*old.obj, &*new.obj);
+        }
+        old
     }
 
     pub fn get_singletone<Q: Any>(&self) -> &Q {
-        self.singletone_map
-            .get(&TypeId::of::<Q>())
-            .unwrap()
-            .downcast_ref()
-            .unwrap()
+        self.try_get_singletone()
+            .unwrap_or_else(|err| panic!("{err}"))
+    }
+
+    pub fn try_get_singletone<Q: Any>(&self) -> Result<&Q, ContextError> {
+        match self.singletone_map.get(&TypeId::of::<Q>()) {
+            Some(entry) => entry.downcast_ref(),
+            None => self
+                .resolve_singletone::<Q>()
+                .ok_or_else(|| ContextError::MissingKey(type_name::<Q>().to_string()))??
+                .downcast_ref(),
+        }
+    }
+
+    // A singleton built by a factory is taken too; the next lookup builds a
+    // fresh one.
+    pub fn take<Q: Any>(&mut self) -> Option<Q> {
+        let id = TypeId::of::<Q>();
+        let entry = match self.singletone_map.remove(&id) {
+            Some(entry) => entry,
+            None => self.factories.get_mut(&id)?.take_instance()?,
+        };
+        entry.obj.downcast().ok().map(|obj| *obj)
+    }
+
+    pub fn insert_as<Q: ?Sized + 'static>(&mut self, obj: Box<Q>) {
+        self.insert_singletone(obj);
+    }
+
+    pub fn get_as<Q: ?Sized + 'static>(&self) -> &Q {
+        self.get_singletone::<Box<Q>>()
+    }
+
+    pub fn try_get_as<Q: ?Sized + 'static>(&self) -> Result<&Q, ContextError> {
+        self.try_get_singletone::<Box<Q>>().map(|obj| &**obj)
+    }
+
+    pub fn get_all<Q: Any>(&self) -> impl Iterator<Item = (&str, &Q)> {
+        self.map
+            .iter()
+            .filter_map(|(key, entry)| Some((key.as_str(), entry.obj.downcast_ref()?)))
     }
 }
@@ -0,0 +1,123 @@
+#![forbid(unsafe_code)]
+
+use std::{
+    any::{Any, TypeId},
+    cell::RefCell,
+    collections::HashMap,
+    rc::{Rc, Weak},
+};
+
+use crate::Context;
+
+type Callback = Rc<RefCell<dyn FnMut(&dyn Any, &dyn Any)>>;
+
+#[derive(Clone, PartialEq, Eq, Hash)]
+enum Topic {
+    Key(String),
+    Singletone(TypeId),
+}
+
+#[derive(Default)]
+struct Registry {
+    next_id: usize,
+    subscribers: HashMap<Topic, Vec<(usize, Callback)>>,
+}
+
+#[derive(Default)]
+pub(crate) struct Observers {
+    registry: Rc<RefCell<Registry>>,
+}
+
+impl Observers {
+    fn subscribe(&self, topic: Topic, callback: Callback) -> Subscription {
+        let mut registry = self.registry.borrow_mut();
+        let id = registry.next_id;
+        registry.next_id += 1;
+        registry
+            .subscribers
+            .entry(topic.clone())
+            .or_default()
+            .push((id, callback));
+
+        Subscription {
+            registry: Rc::downgrade(&self.registry),
+            topic,
+            id,
+        }
+    }
+
+    fn notify(&self, topic: &Topic, old: &dyn Any, new: &dyn Any) {
+        let callbacks: Vec<_> = match self.registry.borrow().subscribers.get(topic) {
+            Some(subscribers) => subscribers.iter().map(|(_, f)| f.clone()).collect(),
+            None => return,
+        };
+
+        for callback in callbacks {
+            (callback.borrow_mut())(old, new);
+        }
+    }
+
+    pub(crate) fn notify_key(&self, key: &str, old: &dyn Any, new: &dyn Any) {
+        self.notify(&Topic::Key(key.to_string()), old, new);
+    }
+
+    pub(crate) fn notify_singletone(&self, id: TypeId, old: &dyn Any, new: &dyn Any) {
+        self.notify(&Topic::Singletone(id), old, new);
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[must_use = "dropping a Subscription unsubscribes the callback"]
+pub struct Subscription {
+    registry: Weak<RefCell<Registry>>,
+    topic: Topic,
+    id: usize,
+}
+
+impl Drop for Subscription {
+    fn drop(&mut self) {
+        let Some(registry) = self.registry.upgrade() else {
+            return;
+        };
+
+        let mut registry = registry.borrow_mut();
+        if let Some(subscribers) = registry.subscribers.get_mut(&self.topic) {
+            subscribers.retain(|(id, _)| *id != self.id);
+            if subscribers.is_empty() {
+                registry.subscribers.remove(&self.topic);
+            }
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+fn typed<Q: Any>(mut f: impl FnMut(&Q, &Q) + 'static) -> Callback {
+    Rc::new(RefCell::new(move |old: &dyn Any, new: &dyn Any| {
+        if let (Some(old), Some(new)) = (old.downcast_ref(), new.downcast_ref()) {
+            f(old, new);
+        }
+    }))
+}
+
+impl Context {
+    pub fn subscribe<K, Q, F>(&self, key: K, f: F) -> Subscription
+    where
+        K: ToString,
+        Q: Any,
+        F: FnMut(&Q, &Q) + 'static,
+    {
+        self.observers
+            .subscribe(Topic::Key(key.to_string()), typed(f))
+    }
+
+    pub fn subscribe_singleton<Q, F>(&self, f: F) -> Subscription
+    where
+        Q: Any,
+        F: FnMut(&Q, &Q) + 'static,
+    {
+        self.observers
+            .subscribe(Topic::Singletone(TypeId::of::<Q>()), typed(f))
+    }
+}
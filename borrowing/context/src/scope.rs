@@ -0,0 +1,79 @@
+#![forbid(unsafe_code)]
+
+use std::{any::TypeId, cell::RefCell};
+
+use crate::{factory::Factory, Context, Entry};
+
+pub(crate) enum Undo {
+    Key(String, Option<Entry>),
+    Singletone(TypeId, Option<Entry>),
+    Factory(TypeId, Option<Factory>),
+    // A singleton built by its factory inside the scope.
+    Instance(TypeId),
+}
+
+// Singletons are resolved through `&Context`, so frames are behind a `RefCell`.
+#[derive(Default)]
+pub(crate) struct Journal {
+    frames: RefCell<Vec<Vec<Undo>>>,
+}
+
+impl Journal {
+    pub(crate) fn is_active(&self) -> bool {
+        !self.frames.borrow().is_empty()
+    }
+
+    pub(crate) fn record(&self, undo: Undo) {
+        if let Some(frame) = self.frames.borrow_mut().last_mut() {
+            frame.push(undo);
+        }
+    }
+}
+
+struct ScopeGuard<'a> {
+    cx: &'a mut Context,
+}
+
+// Restored values reach subscribers just like the overrides did.
+impl Drop for ScopeGuard<'_> {
+    fn drop(&mut self) {
+        let frame = self.cx.journal.frames.get_mut().pop().unwrap_or_default();
+        for undo in frame.into_iter().rev() {
+            match undo {
+                Undo::Key(key, Some(entry)) => {
+                    self.cx.replace_entry(key, entry);
+                }
+                Undo::Key(key, None) => {
+                    self.cx.map.remove(&key);
+                }
+                Undo::Singletone(id, Some(entry)) => {
+                    self.cx.replace_singletone(id, entry);
+                }
+                Undo::Singletone(id, None) => {
+                    self.cx.singletone_map.remove(&id);
+                }
+                Undo::Factory(id, Some(factory)) => {
+                    self.cx.factories.insert(id, factory);
+                }
+                Undo::Factory(id, None) => {
+                    self.cx.factories.remove(&id);
+                }
+                Undo::Instance(id) => {
+                    if let Some(factory) = self.cx.factories.get_mut(&id) {
+                        factory.take_instance();
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl Context {
+    // Values changed in place through `get_mut` or moved out with `take`
+    // can't be put back and are left as they are.
+    pub fn scope<R, F: FnOnce(&mut Context) -> R>(&mut self, f: F) -> R {
+        self.journal.frames.get_mut().push(vec![]);
+        let guard = ScopeGuard { cx: self };
+        f(guard.cx)
+    }
+}
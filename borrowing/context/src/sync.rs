@@ -0,0 +1,121 @@
+#![forbid(unsafe_code)]
+
+use std::{
+    any::{type_name, Any, TypeId},
+    collections::HashMap,
+    sync::{Arc, PoisonError, RwLock},
+};
+
+use crate::ContextError;
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Clone)]
+struct SyncEntry {
+    obj: Arc<dyn Any + Send + Sync>,
+    type_name: &'static str,
+}
+
+impl SyncEntry {
+    fn new<Q: Any + Send + Sync>(obj: Q) -> Self {
+        Self {
+            obj: Arc::new(obj),
+            type_name: type_name::<Q>(),
+        }
+    }
+
+    fn downcast<Q: Any + Send + Sync>(self) -> Result<Arc<Q>, ContextError> {
+        self.obj.downcast().map_err(|_| ContextError::TypeMismatch {
+            expected: type_name::<Q>(),
+            actual_type_name: self.type_name,
+        })
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Default)]
+pub struct SyncContext {
+    map: RwLock<HashMap<String, SyncEntry>>,
+    singletone_map: RwLock<HashMap<TypeId, SyncEntry>>,
+}
+
+impl SyncContext {
+    pub fn new() -> Self {
+        Self {
+            map: RwLock::new(HashMap::new()),
+            singletone_map: RwLock::new(HashMap::new()),
+        }
+    }
+
+    pub fn insert<K: ToString, Q: Any + Send + Sync>(&self, key: K, obj: Q) {
+        self.map
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(key.to_string(), SyncEntry::new(obj));
+    }
+
+    pub fn get<Q: Any + Send + Sync>(&self, key: &str) -> Arc<Q> {
+        self.try_get(key).unwrap_or_else(|err| panic!("{err}"))
+    }
+
+    pub fn try_get<Q: Any + Send + Sync>(&self, key: &str) -> Result<Arc<Q>, ContextError> {
+        let entry = self
+            .map
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(key)
+            .cloned();
+        entry
+            .ok_or_else(|| ContextError::MissingKey(key.to_string()))?
+            .downcast()
+    }
+
+    pub fn contains(&self, key: &str) -> bool {
+        self.map
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .contains_key(key)
+    }
+
+    pub fn remove(&self, key: &str) -> bool {
+        self.map
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .remove(key)
+            .is_some()
+    }
+
+    pub fn insert_singletone<Q: Any + Send + Sync>(&self, obj: Q) {
+        self.singletone_map
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(TypeId::of::<Q>(), SyncEntry::new(obj));
+    }
+
+    pub fn get_singletone<Q: Any + Send + Sync>(&self) -> Arc<Q> {
+        self.try_get_singletone()
+            .unwrap_or_else(|err| panic!("{err}"))
+    }
+
+    pub fn try_get_singletone<Q: Any + Send + Sync>(&self) -> Result<Arc<Q>, ContextError> {
+        let entry = self
+            .singletone_map
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(&TypeId::of::<Q>())
+            .cloned();
+        entry
+            .ok_or_else(|| ContextError::MissingKey(type_name::<Q>().to_string()))?
+            .downcast()
+    }
+
+    pub fn take<Q: Any + Send + Sync>(&self) -> Option<Arc<Q>> {
+        self.singletone_map
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .remove(&TypeId::of::<Q>())?
+            .downcast()
+            .ok()
+    }
+}
@@ -1,4 +1,12 @@
-use context::Context;
+use context::{ConfigError, ConfigLoader, Context, ContextError, Lifetime, SyncContext, TypedKey};
+use std::{
+    cell::{Cell, RefCell},
+    io::Write,
+    panic::{catch_unwind, AssertUnwindSafe},
+    rc::Rc,
+    sync::Arc,
+    thread,
+};
 
 ////////////////////////////////////////////////////////////////////////////////
 
@@ -88,3 +96,608 @@ fn wrong_type() {
     cx.insert("greeter", Greeter {});
     cx.get::<usize>("greeter");
 }
+
+#[test]
+fn try_get() {
+    let mut cx = Context::new();
+    cx.insert("greeter", Greeter {});
+    cx.insert_singletone(5usize);
+
+    assert_eq!(cx.try_get::<Greeter>("greeter").unwrap().say_hi(), "hi!");
+    assert_eq!(
+        cx.try_get::<usize>("missing").err(),
+        Some(ContextError::MissingKey("missing".to_string()))
+    );
+    assert_eq!(
+        cx.try_get::<usize>("greeter").err(),
+        Some(ContextError::TypeMismatch {
+            expected: "usize",
+            actual_type_name: "tests::Greeter",
+        })
+    );
+
+    assert_eq!(*cx.try_get_singletone::<usize>().unwrap(), 5);
+    assert_eq!(
+        cx.try_get_singletone::<i32>().err(),
+        Some(ContextError::MissingKey("i32".to_string()))
+    );
+    assert_eq!(
+        cx.try_get::<usize>("missing").unwrap_err().to_string(),
+        "missing key \"missing\""
+    );
+}
+
+#[test]
+fn modify() {
+    let mut cx = Context::new();
+    cx.insert("counter", 1u32);
+    *cx.get_mut::<u32>("counter").unwrap() += 1;
+    assert_eq!(*cx.get::<u32>("counter"), 2);
+    assert!(cx.get_mut::<i64>("counter").is_err());
+
+    assert!(cx.contains("counter"));
+    assert!(cx.remove("counter"));
+    assert!(!cx.contains("counter"));
+    assert!(!cx.remove("counter"));
+
+    cx.insert_singletone(String::from("service"));
+    assert_eq!(cx.take::<String>().as_deref(), Some("service"));
+    assert!(cx.take::<String>().is_none());
+    assert!(cx.try_get_singletone::<String>().is_err());
+}
+
+#[test]
+fn sync_context() {
+    let cx = SyncContext::new();
+    cx.insert("workers", 4usize);
+    cx.insert_singletone(String::from("db"));
+    cx.insert_singletone(Box::new(Greeter {}) as Box<dyn SayHi + Send + Sync>);
+
+    thread::scope(|s| {
+        for i in 0..4 {
+            let cx = &cx;
+            s.spawn(move || {
+                assert_eq!(*cx.get::<usize>("workers"), 4);
+                assert_eq!(cx.get_singletone::<String>().as_str(), "db");
+                let greeter = cx.get_singletone::<Box<dyn SayHi + Send + Sync>>();
+                assert_eq!(greeter.say_hi(), "hi!");
+                cx.insert(format!("worker-{i}"), i);
+            });
+        }
+    });
+
+    for i in 0..4 {
+        assert_eq!(*cx.get::<i32>(&format!("worker-{i}")), i);
+    }
+
+    let db = cx.get_singletone::<String>();
+    cx.insert_singletone(String::from("replica"));
+    assert_eq!(db.as_str(), "db");
+    assert!(Arc::ptr_eq(
+        &cx.get_singletone::<String>(),
+        &cx.take::<String>().unwrap()
+    ));
+
+    assert!(matches!(
+        cx.try_get::<String>("workers"),
+        Err(ContextError::TypeMismatch { .. })
+    ));
+    assert!(cx.remove("workers"));
+    assert!(!cx.contains("workers"));
+}
+
+#[test]
+fn factories() {
+    struct Config {
+        url: String,
+    }
+    struct Database {
+        url: String,
+    }
+    struct Request {
+        id: usize,
+    }
+
+    let built = Rc::new(Cell::new(0));
+    let mut cx = Context::new();
+    cx.insert_singletone(Config {
+        url: "postgres://".to_string(),
+    });
+    cx.register_factory({
+        let built = built.clone();
+        move |cx| {
+            built.set(built.get() + 1);
+            let config = cx.try_get_singletone::<Config>()?;
+            Ok(Database {
+                url: config.url.clone(),
+            })
+        }
+    });
+    cx.register_factory_with(Lifetime::Transient, {
+        let counter = Cell::new(0);
+        move |_| {
+            counter.set(counter.get() + 1);
+            Ok(Request { id: counter.get() })
+        }
+    });
+
+    assert_eq!(built.get(), 0);
+    assert_eq!(cx.get_singletone::<Database>().url, "postgres://");
+    assert_eq!(cx.get_singletone::<Database>().url, "postgres://");
+    assert_eq!(built.get(), 1);
+
+    assert_eq!(cx.create::<Request>().unwrap().id, 1);
+    assert_eq!(cx.create::<Request>().unwrap().id, 2);
+    assert!(cx.try_get_singletone::<Request>().is_err());
+    assert!(cx.create::<Database>().is_err());
+}
+
+#[test]
+fn factory_cycle() {
+    struct A;
+    struct B;
+    struct C;
+
+    let mut cx = Context::new();
+    cx.register_factory(|cx| cx.try_get_singletone::<B>().map(|_| A));
+    cx.register_factory(|cx| cx.try_get_singletone::<C>().map(|_| B));
+    cx.register_factory(|cx| cx.try_get_singletone::<A>().map(|_| C));
+
+    let err = cx.try_get_singletone::<B>().err().unwrap();
+    assert_eq!(
+        err,
+        ContextError::Cycle(vec![
+            "tests::factory_cycle::B",
+            "tests::factory_cycle::C",
+            "tests::factory_cycle::A",
+            "tests::factory_cycle::B"
+        ])
+    );
+    assert_eq!(
+        err.to_string(),
+        "dependency cycle: tests::factory_cycle::B -> tests::factory_cycle::C -> tests::factory_cycle::A -> tests::factory_cycle::B"
+    );
+
+    cx.insert_singletone(C);
+    assert!(cx.try_get_singletone::<A>().is_ok());
+}
+
+#[test]
+fn child_context() {
+    let mut app = Context::new();
+    app.insert("name", "app");
+    app.insert("port", 80u16);
+    app.insert_singletone(1u8);
+    app.register_factory(|_| Ok(String::from("lazy")));
+
+    {
+        let mut request = app.child();
+        assert_eq!(*request.get::<&str>("name"), "app");
+        assert_eq!(request.get_singletone::<String>(), "lazy");
+
+        request.insert("name", "request");
+        request.insert("user", 42u64);
+        request.insert_singletone(2u8);
+        assert_eq!(*request.get::<&str>("name"), "request");
+        assert_eq!(*request.get::<u16>("port"), 80);
+        assert_eq!(*request.get::<u64>("user"), 42);
+        assert_eq!(*request.get_singletone::<u8>(), 2);
+        assert!(request.contains("port"));
+        assert!(request.try_get::<u16>("name").is_err());
+
+        assert!(request.remove("name"));
+        assert_eq!(*request.get::<&str>("name"), "app");
+        assert!(!request.remove("port"));
+        assert_eq!(request.take::<u8>(), Some(2));
+        assert_eq!(*request.get_singletone::<u8>(), 1);
+        assert_eq!(*request.parent().get::<&str>("name"), "app");
+    }
+
+    assert!(!app.contains("user"));
+    assert_eq!(*app.get::<&str>("name"), "app");
+    assert_eq!(*app.get_singletone::<u8>(), 1);
+}
+
+#[test]
+fn typed_keys() {
+    const PORT: TypedKey<u16> = TypedKey::new("port");
+    const HOST: TypedKey<String> = TypedKey::new("host");
+    const PORT_AS_STR: TypedKey<&str> = TypedKey::new("port");
+
+    let mut cx = Context::new();
+    assert!(cx.get_typed(&PORT).is_none());
+    cx.insert_typed(&PORT, 8080);
+    cx.insert_typed(&HOST, "localhost".to_string());
+    assert_eq!(cx.get_typed(&PORT), Some(&8080));
+    assert_eq!(*cx.get::<u16>(PORT.name()), 8080);
+    assert!(cx.get_typed(&PORT_AS_STR).is_none());
+
+    cx.get_typed_mut(&HOST).unwrap().push_str(":80");
+    assert_eq!(cx.get_typed(&HOST).unwrap(), "localhost:80");
+
+    let mut child = cx.child();
+    child.insert_typed(&PORT, 9090);
+    assert_eq!(child.get_typed(&PORT), Some(&9090));
+    assert_eq!(child.get_typed(&HOST).unwrap(), "localhost:80");
+}
+
+#[test]
+fn introspection() {
+    let mut cx = Context::new();
+    cx.insert("greeter", Greeter {});
+    cx.insert_debug("port", 8080u16);
+    cx.insert_debug("hosts", vec!["a", "b"]);
+    cx.insert_singletone(1u8);
+    cx.insert_singletone(String::new());
+
+    let mut keys: Vec<_> = cx.keys().collect();
+    keys.sort_unstable();
+    assert_eq!(keys, ["greeter", "hosts", "port"]);
+    assert_eq!(cx.type_name_of("greeter"), Some("tests::Greeter"));
+    assert_eq!(cx.type_name_of("missing"), None);
+    assert_eq!(*cx.get::<u16>("port"), 8080);
+
+    let mut singletons: Vec<_> = cx.singleton_types().collect();
+    singletons.sort_unstable();
+    assert_eq!(singletons, ["alloc::string::String", "u8"]);
+
+    assert_eq!(
+        format!("{cx:?}"),
+        "Context { keys: {\"greeter\": tests::Greeter, \
+         \"hosts\": alloc::vec::Vec<&str> = [\"a\", \"b\"], \
+         \"port\": u16 = 8080}, \
+         singletons: [\"alloc::string::String\", \"u8\"] }"
+    );
+}
+
+#[test]
+fn trait_objects() {
+    struct Polite;
+
+    impl SayHi for Polite {
+        fn say_hi(&self) -> &str {
+            "good day!"
+        }
+    }
+
+    let mut cx = Context::new();
+    assert!(cx.try_get_as::<dyn SayHi>().is_err());
+    cx.insert_as::<dyn SayHi>(Box::new(Greeter {}));
+    assert_eq!(cx.get_as::<dyn SayHi>().say_hi(), "hi!");
+    cx.insert_as::<dyn SayHi>(Box::new(Polite));
+    assert_eq!(cx.try_get_as::<dyn SayHi>().unwrap().say_hi(), "good day!");
+    cx.insert_as::<[u8]>(Box::new(*b"raw"));
+    assert_eq!(cx.get_as::<[u8]>(), b"raw");
+
+    cx.insert("first", 1u32);
+    cx.insert("second", 2u32);
+    cx.insert("other", 3u64);
+    cx.insert("greeter", Box::new(Greeter {}) as Box<dyn SayHi>);
+    cx.insert("polite", Box::new(Polite) as Box<dyn SayHi>);
+
+    let mut numbers: Vec<_> = cx.get_all::<u32>().collect();
+    numbers.sort_unstable();
+    assert_eq!(numbers, [("first", &1), ("second", &2)]);
+
+    let mut greetings: Vec<_> = cx
+        .get_all::<Box<dyn SayHi>>()
+        .map(|(_, greeter)| greeter.say_hi())
+        .collect();
+    greetings.sort_unstable();
+    assert_eq!(greetings, ["good day!", "hi!"]);
+    assert_eq!(cx.get_all::<i8>().count(), 0);
+}
+
+#[test]
+fn subscriptions() {
+    let changes = Rc::new(RefCell::new(vec![]));
+    let mut cx = Context::new();
+    cx.insert("port", 80u16);
+
+    let port = cx.subscribe("port", {
+        let changes = changes.clone();
+        move |old: &u16, new: &u16| changes.borrow_mut().push(format!("port {old} -> {new}"))
+    });
+    let name = cx.subscribe_singleton({
+        let changes = changes.clone();
+        move |old: &String, new: &String| changes.borrow_mut().push(format!("{old} -> {new}"))
+    });
+
+    cx.insert("port", 8080u16);
+    cx.insert("other", 1u16);
+    cx.insert_singletone(String::from("a"));
+    cx.insert_singletone(String::from("b"));
+    cx.insert("port", "not a number");
+    cx.insert("port", 443u16);
+    assert_eq!(*changes.borrow(), ["port 80 -> 8080", "a -> b"]);
+
+    drop(port);
+    cx.insert("port", 80u16);
+    cx.insert_singletone(String::from("c"));
+    assert_eq!(changes.borrow().len(), 3);
+    assert_eq!(changes.borrow()[2], "b -> c");
+
+    drop(name);
+    drop(cx);
+    assert_eq!(Rc::strong_count(&changes), 1);
+}
+
+#[test]
+fn scope() {
+    let mut cx = Context::new();
+    cx.insert("db", "postgres");
+    cx.insert("port", 80u16);
+    cx.insert_singletone(1u8);
+
+    let answer = cx.scope(|cx| {
+        cx.insert("db", "sqlite");
+        cx.insert("db", "in-memory");
+        cx.insert("mock", true);
+        assert!(cx.remove("port"));
+        cx.insert_singletone(2u8);
+        cx.insert_singletone(3i32);
+        cx.register_factory(|_| Ok(String::from("fake")));
+
+        cx.scope(|cx| {
+            cx.insert("db", "nested");
+            cx.insert_singletone(4u8);
+        });
+        assert_eq!(*cx.get::<&str>("db"), "in-memory");
+        assert_eq!(*cx.get_singletone::<u8>(), 2);
+        assert_eq!(cx.get_singletone::<String>(), "fake");
+        42
+    });
+
+    assert_eq!(answer, 42);
+    assert_eq!(*cx.get::<&str>("db"), "postgres");
+    assert_eq!(*cx.get::<u16>("port"), 80);
+    assert!(!cx.contains("mock"));
+    assert_eq!(*cx.get_singletone::<u8>(), 1);
+    assert!(cx.try_get_singletone::<i32>().is_err());
+    assert!(cx.try_get_singletone::<String>().is_err());
+}
+
+#[test]
+fn scope_panic() {
+    let mut cx = Context::new();
+    cx.insert("db", "postgres");
+
+    let res = catch_unwind(AssertUnwindSafe(|| {
+        cx.scope(|cx| {
+            cx.insert("db", "sqlite");
+            cx.insert_singletone(1u8);
+            panic!("test failed");
+        })
+    }));
+
+    assert!(res.is_err());
+    assert_eq!(*cx.get::<&str>("db"), "postgres");
+    assert!(cx.try_get_singletone::<u8>().is_err());
+
+    cx.insert("db", "mysql");
+    cx.scope(|_| {});
+    assert_eq!(*cx.get::<&str>("db"), "mysql");
+}
+
+#[test]
+fn scope_factory_instance() {
+    struct Db(String);
+
+    let mut cx = Context::new();
+    cx.insert("url", "prod".to_string());
+    cx.register_factory(|cx| Ok(Db(cx.try_get::<String>("url")?.clone())));
+
+    cx.scope(|cx| {
+        cx.insert("url", "mock".to_string());
+        assert_eq!(cx.get_singletone::<Db>().0, "mock");
+    });
+    assert_eq!(cx.get::<String>("url"), "prod");
+    assert_eq!(cx.get_singletone::<Db>().0, "prod");
+
+    cx.scope(|cx| {
+        cx.insert("url", "mock".to_string());
+        assert_eq!(cx.get_singletone::<Db>().0, "prod");
+    });
+    assert_eq!(cx.get_singletone::<Db>().0, "prod");
+}
+
+#[test]
+fn take_factory_instance() {
+    let built = Rc::new(Cell::new(0));
+    let mut cx = Context::new();
+    let counter = built.clone();
+    cx.register_factory(move |_| {
+        counter.set(counter.get() + 1);
+        Ok(format!("x{}", counter.get()))
+    });
+    assert!(cx.take::<String>().is_none());
+
+    assert_eq!(cx.get_singletone::<String>(), "x1");
+    assert_eq!(cx.take::<String>().as_deref(), Some("x1"));
+    assert!(cx.take::<String>().is_none());
+    assert_eq!(cx.get_singletone::<String>(), "x2");
+
+    cx.scope(|cx| {
+        assert_eq!(cx.take::<String>().as_deref(), Some("x2"));
+        assert_eq!(cx.get_singletone::<String>(), "x3");
+    });
+    assert_eq!(cx.get_singletone::<String>(), "x4");
+    assert_eq!(built.get(), 4);
+}
+
+#[test]
+fn scope_notifies_restores() {
+    let mut cx = Context::new();
+    cx.insert("port", 80u16);
+    cx.insert_singletone(1u8);
+
+    let seen = Rc::new(RefCell::new(vec![]));
+    let log = seen.clone();
+    let _port = cx.subscribe("port", move |old: &u16, new: &u16| {
+        log.borrow_mut().push((*old, *new));
+    });
+    let levels = Rc::new(RefCell::new(vec![]));
+    let log = levels.clone();
+    let _level = cx.subscribe_singleton(move |old: &u8, new: &u8| {
+        log.borrow_mut().push((*old, *new));
+    });
+
+    cx.scope(|cx| {
+        cx.insert("port", 1u16);
+        cx.insert_singletone(2u8);
+    });
+    assert_eq!(*seen.borrow(), [(80, 1), (1, 80)]);
+    assert_eq!(*levels.borrow(), [(1, 2), (2, 1)]);
+}
+
+fn config_loader() -> ConfigLoader {
+    let mut loader = ConfigLoader::new();
+    loader.register_parser::<u64>("port");
+    loader.register_parser::<String>("host");
+    loader.register_parser::<bool>("debug");
+    loader.register_parser_with("level", |s| match s {
+        "low" => Ok(1u8),
+        "high" => Ok(2u8),
+        _ => Err(format!("unknown level {s:?}")),
+    });
+    loader
+}
+
+#[test]
+fn config_env() {
+    let loader = config_loader();
+    let mut cx = Context::new();
+    cx.insert("port", 1u64);
+
+    loader
+        .load_env(
+            &mut cx,
+            "# comment\nport=8080\nexport host = \"localhost\"\n\nlevel=high\nunused=1\n",
+        )
+        .unwrap();
+    assert_eq!(*cx.get::<u64>("port"), 8080);
+    assert_eq!(cx.get::<String>("host"), "localhost");
+    assert_eq!(*cx.get::<u8>("level"), 2);
+    assert!(!cx.contains("unused"));
+    assert!(!cx.contains("debug"));
+
+    let err = loader
+        .load_env(&mut cx, "port=80\n\ndebug=yes\n")
+        .unwrap_err();
+    match &err {
+        ConfigError::Value { key, line, .. } => assert_eq!((key.as_str(), *line), ("debug", 3)),
+        _ => panic!("unexpected error {err}"),
+    }
+    assert_eq!(*cx.get::<u64>("port"), 8080);
+
+    let err = loader.load_env(&mut cx, "port=80\nlevel\n").unwrap_err();
+    assert!(matches!(err, ConfigError::Syntax { line: 2, .. }));
+    assert_eq!(
+        loader
+            .load_env(&mut cx, "level=mid")
+            .unwrap_err()
+            .to_string(),
+        "line 1: level: unknown level \"mid\""
+    );
+
+    let mut file = tempfile::Builder::new().suffix(".env").tempfile().unwrap();
+    writeln!(file, "debug=true").unwrap();
+    loader.load_file(&mut cx, file.path()).unwrap();
+    assert!(*cx.get::<bool>("debug"));
+}
+
+#[cfg(not(all(feature = "toml", feature = "json")))]
+#[test]
+fn config_feature_disabled() {
+    let loader = config_loader();
+    let mut cx = Context::new();
+    let disabled = [
+        ("toml", cfg!(feature = "toml")),
+        ("json", cfg!(feature = "json")),
+    ];
+    for (ext, _) in disabled.into_iter().filter(|(_, enabled)| !enabled) {
+        let mut file = tempfile::Builder::new()
+            .suffix(&format!(".{ext}"))
+            .tempfile()
+            .unwrap();
+        writeln!(file, "port=1").unwrap();
+        match loader.load_file(&mut cx, file.path()) {
+            Err(ConfigError::FeatureDisabled(feature)) => assert_eq!(feature, ext),
+            other => panic!("unexpected result {other:?}"),
+        }
+    }
+    assert!(!cx.contains("port"));
+}
+
+#[cfg(feature = "toml")]
+#[test]
+fn config_toml() {
+    let mut loader = config_loader();
+    loader.register_parser::<u16>("server.port");
+    let mut cx = Context::new();
+
+    loader
+        .load_toml(
+            &mut cx,
+            "port = 0x50\nhost = \"example.com\"\n\n[server]\nport = 9000\n",
+        )
+        .unwrap();
+    assert_eq!(*cx.get::<u64>("port"), 80);
+    assert_eq!(cx.get::<String>("host"), "example.com");
+    assert_eq!(*cx.get::<u16>("server.port"), 9000);
+
+    let err = loader
+        .load_toml(&mut cx, "host = \"a\"\n[server]\nport = 70000\n")
+        .unwrap_err();
+    match &err {
+        ConfigError::Value { key, line, .. } => {
+            assert_eq!((key.as_str(), *line), ("server.port", 3))
+        }
+        _ => panic!("unexpected error {err}"),
+    }
+
+    loader.load_toml(&mut cx, "ports = [1, 2]\n").unwrap();
+    let err = loader.load_toml(&mut cx, "port = [1, 2]\n").unwrap_err();
+    assert!(matches!(err, ConfigError::Value { line: 1, .. }));
+
+    let err = loader
+        .load_toml(&mut cx, "port = 1\nhost = \n")
+        .unwrap_err();
+    assert!(matches!(err, ConfigError::Syntax { line: 2, .. }));
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn config_json() {
+    let mut loader = config_loader();
+    loader.register_parser::<u16>("server.port");
+    let mut cx = Context::new();
+
+    let source =
+        "{\n  \"port\": 8080,\n  \"debug\": true,\n  \"server\": {\n    \"port\": 9000\n  }\n}";
+    loader.load_json(&mut cx, source).unwrap();
+    assert_eq!(*cx.get::<u64>("port"), 8080);
+    assert!(*cx.get::<bool>("debug"));
+    assert_eq!(*cx.get::<u16>("server.port"), 9000);
+
+    let source = "{\n  \"port\": 1,\n  \"server\": {\n    \"port\": -5\n  }\n}";
+    let err = loader.load_json(&mut cx, source).unwrap_err();
+    match &err {
+        ConfigError::Value { key, line, .. } => {
+            assert_eq!((key.as_str(), *line), ("server.port", 4))
+        }
+        _ => panic!("unexpected error {err}"),
+    }
+
+    let source = "{\n  \"a\": {\"port\": 1, \"list\": [{\"port\": 2}]},\n  \"b\": \"\\\"port\\\": {\",\n\n  \"port\": \"x\"\n}";
+    let err = loader.load_json(&mut cx, source).unwrap_err();
+    match &err {
+        ConfigError::Value { key, line, .. } => assert_eq!((key.as_str(), *line), ("port", 5)),
+        _ => panic!("unexpected error {err}"),
+    }
+
+    let err = loader
+        .load_json(&mut cx, "{\n  \"port\": 1,\n}")
+        .unwrap_err();
+    assert!(matches!(err, ConfigError::Syntax { line: 3, .. }));
+}
@@ -0,0 +1,182 @@
+#![forbid(unsafe_code)]
+use std::{collections::HashMap, rc::Rc};
+
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::{PRef, PStack, Versions};
+
+impl<T: Serialize> Serialize for PRef<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.data.serialize(serializer)
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Serialize)]
+struct Encoded<T, V> {
+    nodes: Vec<(usize, PRef<T>)>,
+    versions: V,
+}
+
+#[derive(Deserialize)]
+struct Decoded<T, V> {
+    nodes: Vec<(usize, T)>,
+    versions: V,
+}
+
+// A node is identified by its value together with the node below it: list
+// operations reuse the same value on top of different tails.
+type NodeKey<T> = (*const T, *const PStack<T>);
+
+struct Encoder<T> {
+    ids: HashMap<NodeKey<T>, usize>,
+    nodes: Vec<(usize, PRef<T>)>,
+}
+
+impl<T> Encoder<T> {
+    fn new() -> Self {
+        Self {
+            ids: HashMap::new(),
+            nodes: vec![],
+        }
+    }
+
+    fn key(stack: &PStack<T>) -> Option<NodeKey<T>> {
+        let top = stack.data.as_ref()?;
+        let prev = stack.prev.as_ref().map_or(std::ptr::null(), Rc::as_ptr);
+        Some((Rc::as_ptr(&top.data), prev))
+    }
+
+    fn id(&mut self, stack: &PStack<T>) -> usize {
+        let mut pending = vec![];
+        let mut parent = 0;
+        let mut curr = stack.clone();
+        while let Some(key) = Self::key(&curr) {
+            if let Some(&id) = self.ids.get(&key) {
+                parent = id;
+                break;
+            }
+            let (top, tail) = curr.pop().unwrap();
+            pending.push((key, top));
+            curr = tail;
+        }
+
+        for (key, top) in pending.into_iter().rev() {
+            self.ids.insert(key, self.nodes.len() + 1);
+            self.nodes.push((parent, top));
+            parent = self.nodes.len();
+        }
+        parent
+    }
+
+    fn finish<V>(self, versions: V) -> Encoded<T, V> {
+        Encoded {
+            nodes: self.nodes,
+            versions,
+        }
+    }
+}
+
+fn decode<T, E: de::Error>(nodes: Vec<(usize, T)>) -> Result<Vec<PStack<T>>, E> {
+    let mut stacks = vec![PStack::new()];
+    for (parent, value) in nodes {
+        let prev = stacks
+            .get(parent)
+            .ok_or_else(|| E::custom(format_args!("unknown parent node {parent}")))?;
+        stacks.push(prev.push(value));
+    }
+    Ok(stacks)
+}
+
+fn lookup<T, E: de::Error>(stacks: &[PStack<T>], id: usize) -> Result<PStack<T>, E> {
+    stacks
+        .get(id)
+        .cloned()
+        .ok_or_else(|| E::custom(format_args!("unknown version node {id}")))
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+pub struct PStackForest<T> {
+    versions: Vec<PStack<T>>,
+}
+
+impl<T> Default for PStackForest<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> PStackForest<T> {
+    pub fn new() -> Self {
+        Self { versions: vec![] }
+    }
+
+    pub fn insert(&mut self, stack: &PStack<T>) -> usize {
+        self.versions.push(stack.clone());
+        self.versions.len() - 1
+    }
+
+    pub fn versions(&self) -> &[PStack<T>] {
+        &self.versions
+    }
+
+    pub fn into_versions(self) -> Vec<PStack<T>> {
+        self.versions
+    }
+}
+
+impl<T> From<Vec<PStack<T>>> for PStackForest<T> {
+    fn from(versions: Vec<PStack<T>>) -> Self {
+        Self { versions }
+    }
+}
+
+impl<T: Serialize> Serialize for PStackForest<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut encoder = Encoder::new();
+        let versions: Vec<_> = self.versions.iter().map(|s| encoder.id(s)).collect();
+        encoder.finish(versions).serialize(serializer)
+    }
+}
+
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for PStackForest<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let decoded = Decoded::<T, Vec<usize>>::deserialize(deserializer)?;
+        let stacks = decode(decoded.nodes)?;
+        let versions = decoded
+            .versions
+            .into_iter()
+            .map(|id| lookup(&stacks, id))
+            .collect::<Result<_, _>>()?;
+        Ok(Self { versions })
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+impl<T: Serialize> Serialize for Versions<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut encoder = Encoder::new();
+        let mut names: Vec<_> = self.names().collect();
+        names.sort_unstable();
+        let versions: Vec<_> = names
+            .into_iter()
+            .map(|name| (name, encoder.id(self.get(name).unwrap())))
+            .collect();
+        encoder.finish(versions).serialize(serializer)
+    }
+}
+
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for Versions<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let decoded = Decoded::<T, Vec<(String, usize)>>::deserialize(deserializer)?;
+        let stacks = decode(decoded.nodes)?;
+        let mut versions = Versions::new();
+        for (name, id) in decoded.versions {
+            versions.record(name, &lookup(&stacks, id)?);
+        }
+        Ok(versions)
+    }
+}
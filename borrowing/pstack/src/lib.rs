@@ -1,5 +1,21 @@
 #![forbid(unsafe_code)]
-use std::rc::Rc;
+use std::{
+    cmp::Ordering,
+    fmt,
+    hash::{Hash, Hasher},
+    rc::{Rc, Weak},
+};
+
+#[cfg(feature = "serde")]
+mod forest;
+mod list;
+mod transient;
+mod versions;
+
+#[cfg(feature = "serde")]
+pub use forest::PStackForest;
+pub use transient::PStackBuilder;
+pub use versions::{Diff, Versions};
 
 pub struct PRef<T> {
     data: Rc<T>,
@@ -21,6 +37,92 @@ impl<T> Clone for PRef<T> {
     }
 }
 
+impl<T> PRef<T> {
+    pub fn ptr_eq(this: &Self, other: &Self) -> bool {
+        Rc::ptr_eq(&this.data, &other.data)
+    }
+
+    pub fn strong_count(this: &Self) -> usize {
+        Rc::strong_count(&this.data)
+    }
+
+    pub fn try_unwrap(this: Self) -> Result<T, Self> {
+        Rc::try_unwrap(this.data).map_err(|data| Self { data })
+    }
+
+    pub fn into_inner(this: Self) -> Option<T> {
+        Rc::into_inner(this.data)
+    }
+
+    pub fn downgrade(this: &Self) -> PWeak<T> {
+        PWeak {
+            data: Rc::downgrade(&this.data),
+        }
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for PRef<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&*self.data, f)
+    }
+}
+
+impl<T: fmt::Display> fmt::Display for PRef<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&*self.data, f)
+    }
+}
+
+impl<T: PartialEq> PartialEq for PRef<T> {
+    fn eq(&self, other: &Self) -> bool {
+        *self.data == *other.data
+    }
+}
+
+impl<T: Eq> Eq for PRef<T> {}
+
+impl<T: PartialOrd> PartialOrd for PRef<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        self.data.partial_cmp(&other.data)
+    }
+}
+
+impl<T: Ord> Ord for PRef<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.data.cmp(&other.data)
+    }
+}
+
+impl<T: Hash> Hash for PRef<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.data.hash(state)
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+pub struct PWeak<T> {
+    data: Weak<T>,
+}
+
+impl<T> PWeak<T> {
+    pub fn upgrade(&self) -> Option<PRef<T>> {
+        self.data.upgrade().map(|data| PRef { data })
+    }
+
+    pub fn strong_count(&self) -> usize {
+        self.data.strong_count()
+    }
+}
+
+impl<T> Clone for PWeak<T> {
+    fn clone(&self) -> Self {
+        Self {
+            data: self.data.clone(),
+        }
+    }
+}
+
 pub struct PStackIter<T> {
     stack: PStack<T>,
 }
@@ -122,4 +224,16 @@ impl<T> PStack<T> {
     pub fn iter(&self) -> impl Iterator<Item = PRef<T>> {
         PStackIter::new(self.clone())
     }
+
+    pub fn transient(&self) -> PStackBuilder<T> {
+        PStackBuilder::from(self.clone())
+    }
+}
+
+impl<T> FromIterator<T> for PStack<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut builder = PStackBuilder::new();
+        builder.extend(iter);
+        builder.persistent()
+    }
 }
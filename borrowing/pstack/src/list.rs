@@ -0,0 +1,88 @@
+#![forbid(unsafe_code)]
+use std::rc::Rc;
+
+use crate::{PRef, PStack};
+
+impl<T> PStack<T> {
+    pub fn cons(&self, value: T) -> Self {
+        self.push(value)
+    }
+
+    pub fn head(&self) -> Option<&T> {
+        self.data.as_deref()
+    }
+
+    pub fn tail(&self) -> Option<Self> {
+        self.pop().map(|(_, tail)| tail)
+    }
+
+    pub fn fold<B, F>(&self, init: B, mut f: F) -> B
+    where
+        F: FnMut(B, &T) -> B,
+    {
+        self.iter().fold(init, |acc, item| f(acc, &item))
+    }
+
+    pub fn map<U, F>(&self, mut f: F) -> PStack<U>
+    where
+        F: FnMut(&T) -> U,
+    {
+        let mapped: Vec<_> = self.iter().map(|item| f(&item)).collect();
+        mapped.into_iter().rev().collect()
+    }
+
+    pub fn filter<F>(&self, mut pred: F) -> Self
+    where
+        F: FnMut(&T) -> bool,
+    {
+        let items: Vec<_> = self.iter().map(|item| (pred(&item), item)).collect();
+        let rejected = match items.iter().rposition(|(keep, _)| !keep) {
+            Some(pos) => pos + 1,
+            None => return self.clone(),
+        };
+
+        let kept = items
+            .into_iter()
+            .take(rejected)
+            .filter_map(|(keep, item)| keep.then_some(item))
+            .collect();
+        let shared = self.drop(rejected);
+        shared.push_refs(kept)
+    }
+
+    pub fn concat(&self, other: &Self) -> Self {
+        other.push_refs(self.iter().collect())
+    }
+
+    pub fn take(&self, n: usize) -> Self {
+        if n >= self.len {
+            return self.clone();
+        }
+
+        PStack::new().push_refs(self.iter().take(n).collect())
+    }
+
+    pub fn drop(&self, n: usize) -> Self {
+        if n >= self.len {
+            return PStack::new();
+        }
+
+        let mut res = self.clone();
+        for _ in 0..n {
+            res = res.pop().unwrap().1;
+        }
+        res
+    }
+
+    fn push_refs(&self, refs: Vec<PRef<T>>) -> Self {
+        let mut res = self.clone();
+        for data in refs.into_iter().rev() {
+            res = PStack {
+                len: res.len + 1,
+                data: Some(data),
+                prev: Some(Rc::new(res)),
+            };
+        }
+        res
+    }
+}
@@ -0,0 +1,70 @@
+#![forbid(unsafe_code)]
+use std::{mem, rc::Rc};
+
+use crate::{PRef, PStack};
+
+pub struct PStackBuilder<T> {
+    stack: PStack<T>,
+}
+
+impl<T> Default for PStackBuilder<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> PStackBuilder<T> {
+    pub fn new() -> Self {
+        Self {
+            stack: PStack::new(),
+        }
+    }
+
+    pub fn push(&mut self, value: T) {
+        let len = self.stack.len + 1;
+        let prev = mem::take(&mut self.stack);
+        self.stack = PStack {
+            data: Some(PRef {
+                data: Rc::new(value),
+            }),
+            prev: Some(Rc::new(prev)),
+            len,
+        };
+    }
+
+    pub fn pop(&mut self) -> Option<PRef<T>> {
+        let top = self.stack.data.take()?;
+        self.stack = match self.stack.prev.take() {
+            Some(prev) => Rc::try_unwrap(prev).unwrap_or_else(|shared| (*shared).clone()),
+            None => PStack::new(),
+        };
+
+        Some(top)
+    }
+
+    pub fn len(&self) -> usize {
+        self.stack.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.stack.is_empty()
+    }
+
+    pub fn persistent(self) -> PStack<T> {
+        self.stack
+    }
+}
+
+impl<T> From<PStack<T>> for PStackBuilder<T> {
+    fn from(stack: PStack<T>) -> Self {
+        Self { stack }
+    }
+}
+
+impl<T> Extend<T> for PStackBuilder<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for value in iter {
+            self.push(value);
+        }
+    }
+}
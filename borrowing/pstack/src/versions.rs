@@ -0,0 +1,105 @@
+#![forbid(unsafe_code)]
+use std::{collections::HashMap, rc::Rc};
+
+use crate::{PRef, PStack};
+
+pub struct Diff<T> {
+    pub popped: Vec<PRef<T>>,
+    pub pushed: Vec<PRef<T>>,
+}
+
+impl<T> PStack<T> {
+    // The same value may sit on top of different tails, so both the top value
+    // and the node below it have to match.
+    pub fn same_version(&self, other: &Self) -> bool {
+        self.len == other.len
+            && match (&self.data, &other.data) {
+                (Some(lhs), Some(rhs)) => PRef::ptr_eq(lhs, rhs),
+                (None, None) => true,
+                _ => false,
+            }
+            && match (&self.prev, &other.prev) {
+                (Some(lhs), Some(rhs)) => Rc::ptr_eq(lhs, rhs),
+                (None, None) => true,
+                _ => false,
+            }
+    }
+
+    pub fn common_ancestor(&self, other: &Self) -> Self {
+        let mut lhs = self.clone();
+        let mut rhs = other.clone();
+        while lhs.len > rhs.len {
+            lhs = lhs.pop().unwrap().1;
+        }
+        while rhs.len > lhs.len {
+            rhs = rhs.pop().unwrap().1;
+        }
+        while !lhs.same_version(&rhs) {
+            lhs = lhs.pop().unwrap().1;
+            rhs = rhs.pop().unwrap().1;
+        }
+
+        lhs
+    }
+
+    pub fn diff(&self, other: &Self) -> Diff<T> {
+        let ancestor = self.common_ancestor(other);
+        let popped = self.iter().take(self.len - ancestor.len).collect();
+        let mut pushed: Vec<_> = other.iter().take(other.len - ancestor.len).collect();
+        pushed.reverse();
+
+        Diff { popped, pushed }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+pub struct Versions<T> {
+    versions: HashMap<String, PStack<T>>,
+}
+
+impl<T> Default for Versions<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Versions<T> {
+    pub fn new() -> Self {
+        Self {
+            versions: HashMap::new(),
+        }
+    }
+
+    pub fn record<K: ToString>(&mut self, name: K, stack: &PStack<T>) -> Option<PStack<T>> {
+        self.versions.insert(name.to_string(), stack.clone())
+    }
+
+    pub fn get(&self, name: &str) -> Option<&PStack<T>> {
+        self.versions.get(name)
+    }
+
+    pub fn remove(&mut self, name: &str) -> Option<PStack<T>> {
+        self.versions.remove(name)
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.versions.keys().map(String::as_str)
+    }
+
+    pub fn len(&self) -> usize {
+        self.versions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.versions.is_empty()
+    }
+
+    pub fn common_ancestor(&self, lhs: &str, rhs: &str) -> Option<PStack<T>> {
+        Some(self.get(lhs)?.common_ancestor(self.get(rhs)?))
+    }
+
+    pub fn diff(&self, from: &str, to: &str) -> Option<Diff<T>> {
+        Some(self.get(from)?.diff(self.get(to)?))
+    }
+}
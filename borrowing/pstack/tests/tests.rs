@@ -1,4 +1,5 @@
-use pstack::PStack;
+use pstack::{PRef, PStack, PStackBuilder, Versions};
+use std::collections::HashSet;
 
 #[test]
 fn simple() {
@@ -75,3 +76,294 @@ fn no_clone() {
         stack = tail;
     }
 }
+
+#[test]
+fn transient() {
+    let mut builder = PStackBuilder::new();
+    assert!(builder.is_empty());
+    builder.extend(0..10);
+    assert_eq!(builder.len(), 10);
+    assert_eq!(*builder.pop().unwrap(), 9);
+
+    let stack = builder.persistent();
+    assert_eq!(stack.len(), 9);
+    for (item, i) in stack.iter().zip((0..9).rev()) {
+        assert_eq!(*item, i);
+    }
+
+    let mut builder = stack.transient();
+    for _ in 0..5 {
+        builder.pop();
+    }
+    builder.push(100);
+    let other = builder.persistent();
+    assert_eq!(
+        other.iter().map(|x| *x).collect::<Vec<_>>(),
+        [100, 3, 2, 1, 0]
+    );
+    assert_eq!(stack.len(), 9);
+    assert_eq!(*stack.pop().unwrap().0, 8);
+}
+
+#[test]
+fn from_iter() {
+    let stack: PStack<_> = (0..1000).collect();
+    assert_eq!(stack.len(), 1000);
+    assert!(stack.iter().map(|x| *x).eq((0..1000).rev()));
+}
+
+#[test]
+fn versions() {
+    let base: PStack<_> = (0..5).collect();
+    let left = base.push(10).push(11);
+    let right = base.pop().unwrap().1.push(20);
+
+    let mut versions = Versions::new();
+    assert!(versions.record("base", &base).is_none());
+    versions.record("left", &left);
+    versions.record("right", &right);
+    assert_eq!(versions.len(), 3);
+
+    let ancestor = versions.common_ancestor("left", "base").unwrap();
+    assert!(ancestor.same_version(&base));
+
+    let ancestor = versions.common_ancestor("left", "right").unwrap();
+    assert_eq!(ancestor.len(), 4);
+    assert!(ancestor.same_version(&base.pop().unwrap().1));
+    assert!(!ancestor.same_version(&(0..4).collect()));
+
+    let diff = versions.diff("left", "right").unwrap();
+    assert_eq!(
+        diff.popped.iter().map(|x| **x).collect::<Vec<_>>(),
+        [11, 10, 4]
+    );
+    assert_eq!(diff.pushed.iter().map(|x| **x).collect::<Vec<_>>(), [20]);
+
+    let diff = versions.diff("base", "base").unwrap();
+    assert!(diff.popped.is_empty() && diff.pushed.is_empty());
+
+    assert!(versions.diff("base", "missing").is_none());
+}
+
+#[test]
+fn unrelated_versions() {
+    let lhs: PStack<_> = (0..3).collect();
+    let rhs: PStack<_> = (0..5).collect();
+    let ancestor = lhs.common_ancestor(&rhs);
+    assert!(ancestor.is_empty());
+
+    let diff = lhs.diff(&rhs);
+    assert_eq!(
+        diff.popped.iter().map(|x| **x).collect::<Vec<_>>(),
+        [2, 1, 0]
+    );
+    assert_eq!(
+        diff.pushed.iter().map(|x| **x).collect::<Vec<_>>(),
+        [0, 1, 2, 3, 4]
+    );
+}
+
+#[test]
+fn versions_with_shared_values() {
+    let s = PStack::new().push(1);
+    let a = s.concat(&PStack::new().push(2));
+    let b = s.concat(&PStack::new().push(3));
+    assert!(PRef::ptr_eq(&a.pop().unwrap().0, &b.pop().unwrap().0));
+    assert!(!a.same_version(&b));
+    assert!(a.same_version(&a.clone()));
+    assert!(a.common_ancestor(&b).is_empty());
+
+    let diff = a.diff(&b);
+    assert_eq!(diff.popped.iter().map(|x| **x).collect::<Vec<_>>(), [1, 2]);
+    assert_eq!(diff.pushed.iter().map(|x| **x).collect::<Vec<_>>(), [3, 1]);
+}
+
+#[test]
+fn pref() {
+    let stack = PStack::new().push("a".to_string()).push("b".to_string());
+    let (top, tail) = stack.pop().unwrap();
+    let (again, _) = stack.pop().unwrap();
+    assert!(PRef::ptr_eq(&top, &again));
+    assert_eq!(PRef::strong_count(&top), 3);
+
+    let (other, _) = PStack::new().push("b".to_string()).pop().unwrap();
+    assert!(!PRef::ptr_eq(&top, &other));
+    assert_eq!(top, other);
+    assert!(top > tail.pop().unwrap().0);
+    assert_eq!(format!("{} {:?}", top, top), "b \"b\"");
+
+    let set: HashSet<_> = [top.clone(), again.clone(), other].into_iter().collect();
+    assert_eq!(set.len(), 1);
+    drop(set);
+
+    let weak = PRef::downgrade(&top);
+    drop(again);
+    let top = PRef::try_unwrap(top).unwrap_err();
+    drop(stack);
+    assert_eq!(weak.strong_count(), 1);
+    assert!(PRef::ptr_eq(&weak.upgrade().unwrap(), &top));
+    assert_eq!(PRef::try_unwrap(top).unwrap(), "b");
+    assert!(weak.upgrade().is_none());
+
+    let (last, rest) = tail.pop().unwrap();
+    drop((tail, rest));
+    assert_eq!(PRef::into_inner(last).as_deref(), Some("a"));
+}
+
+#[test]
+fn list() {
+    let list = PStack::new().cons(3).cons(2).cons(1);
+    assert_eq!(list.head(), Some(&1));
+    assert_eq!(list.tail().unwrap().head(), Some(&2));
+    assert!(PStack::<i32>::new().tail().is_none());
+    assert_eq!(list.fold(0, |acc, x| acc * 10 + x), 123);
+
+    let doubled = list.map(|x| x * 2);
+    assert_eq!(doubled.iter().map(|x| *x).collect::<Vec<_>>(), [2, 4, 6]);
+
+    let joined = list.concat(&doubled);
+    assert_eq!(
+        joined.iter().map(|x| *x).collect::<Vec<_>>(),
+        [1, 2, 3, 2, 4, 6]
+    );
+    assert!(joined.drop(3).same_version(&doubled));
+
+    assert_eq!(list.take(2).iter().map(|x| *x).collect::<Vec<_>>(), [1, 2]);
+    assert!(list.take(5).same_version(&list));
+    assert!(list.drop(5).is_empty());
+    assert!(PRef::ptr_eq(
+        &list.take(1).pop().unwrap().0,
+        &list.pop().unwrap().0
+    ));
+}
+
+#[test]
+fn list_sharing() {
+    let list: PStack<_> = (0..10).collect();
+    let suffix = list.drop(4);
+    assert_eq!(suffix.len(), 6);
+    assert!(suffix.same_version(&list.tail().unwrap().tail().unwrap().drop(2)));
+
+    assert!(list.filter(|_| true).same_version(&list));
+    let evens = list.filter(|x| x % 2 == 0);
+    assert_eq!(
+        evens.iter().map(|x| *x).collect::<Vec<_>>(),
+        [8, 6, 4, 2, 0]
+    );
+
+    let small = list.filter(|x| *x != 7);
+    assert_eq!(small.len(), 9);
+    assert!(small.drop(2).same_version(&list.drop(3)));
+}
+
+#[test]
+fn list_versions() {
+    let list: PStack<_> = (0..3).collect();
+    let filtered = list.filter(|x| *x != 1);
+    let taken = list.take(2);
+    assert!(!filtered.same_version(&taken));
+    assert!(!taken.same_version(&list.drop(1)));
+
+    let diff = list.diff(&filtered);
+    assert_eq!(diff.popped.iter().map(|x| **x).collect::<Vec<_>>(), [2, 1]);
+    assert_eq!(diff.pushed.iter().map(|x| **x).collect::<Vec<_>>(), [2]);
+
+    let diff = list.diff(&taken);
+    assert!(taken.common_ancestor(&list).is_empty());
+    assert_eq!(diff.popped.len(), 3);
+    assert_eq!(diff.pushed.iter().map(|x| **x).collect::<Vec<_>>(), [1, 2]);
+
+    let other: PStack<_> = (10..12).collect();
+    let lhs = list.concat(&other);
+    let rhs = list.concat(&other.drop(1));
+    assert!(lhs.common_ancestor(&rhs).same_version(&other.drop(1)));
+    assert!(lhs.common_ancestor(&other).same_version(&other));
+    let diff = lhs.diff(&other);
+    assert_eq!(
+        diff.popped.iter().map(|x| **x).collect::<Vec<_>>(),
+        [2, 1, 0]
+    );
+    assert!(diff.pushed.is_empty());
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn serde_forest() {
+    use pstack::PStackForest;
+
+    let base: PStack<_> = (0..100).map(|x| x.to_string()).collect();
+    let left = base.push("left".to_string());
+    let right = base.push("right".to_string()).push("top".to_string());
+    let forest = PStackForest::from(vec![base.clone(), left, right, base, PStack::new()]);
+
+    let json = serde_json::to_string(&forest).unwrap();
+    let decoded: PStackForest<String> = serde_json::from_str(&json).unwrap();
+    let decoded = decoded.into_versions();
+    assert_eq!(decoded.len(), 5);
+    assert!(decoded[4].is_empty());
+    assert!(decoded[0].same_version(&decoded[3]));
+    assert!(decoded[1].tail().unwrap().same_version(&decoded[0]));
+    assert!(decoded[2].drop(2).same_version(&decoded[0]));
+    assert!(PRef::ptr_eq(
+        &decoded[1].drop(50).pop().unwrap().0,
+        &decoded[2].drop(51).pop().unwrap().0
+    ));
+    assert_eq!(
+        decoded[2]
+            .iter()
+            .map(|x| x.to_string())
+            .take(3)
+            .collect::<Vec<_>>(),
+        ["top", "right", "99"]
+    );
+
+    let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+    assert_eq!(value["nodes"].as_array().unwrap().len(), 103);
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn serde_versions() {
+    let base: PStack<_> = (0..10).collect();
+    let mut versions = Versions::new();
+    versions.record("base", &base);
+    versions.record("edit", &base.drop(3).push(42));
+
+    let json = serde_json::to_string(&versions).unwrap();
+    let decoded: Versions<i32> = serde_json::from_str(&json).unwrap();
+    let diff = decoded.diff("base", "edit").unwrap();
+    assert_eq!(
+        diff.popped.iter().map(|x| **x).collect::<Vec<_>>(),
+        [9, 8, 7]
+    );
+    assert_eq!(diff.pushed.iter().map(|x| **x).collect::<Vec<_>>(), [42]);
+
+    let broken = r#"{"nodes":[[0,1],[5,2]],"versions":[["a",1]]}"#;
+    assert!(serde_json::from_str::<Versions<i32>>(broken).is_err());
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn serde_list_ops() {
+    use pstack::PStackForest;
+
+    let list: PStack<_> = (0..3).collect();
+    let other: PStack<_> = (10..12).collect();
+    let stacks = vec![
+        list.clone(),
+        list.filter(|x| *x != 1),
+        list.take(2),
+        list.concat(&other),
+        other.clone(),
+    ];
+
+    let json = serde_json::to_string(&PStackForest::from(stacks.clone())).unwrap();
+    let decoded: PStackForest<i32> = serde_json::from_str(&json).unwrap();
+    let decoded = decoded.into_versions();
+    assert_eq!(decoded.len(), stacks.len());
+    for (decoded, stack) in decoded.iter().zip(&stacks) {
+        assert_eq!(decoded.len(), stack.len());
+        assert!(decoded.iter().eq(stack.iter()));
+    }
+    assert!(decoded[3].drop(3).same_version(&decoded[4]));
+}
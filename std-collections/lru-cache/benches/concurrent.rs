@@ -0,0 +1,74 @@
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use lru_cache::{ConcurrentLRUCache, LRUCache};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+const OPS_PER_THREAD: usize = 20_000;
+const CAPACITY: usize = 10_000;
+
+trait SharedCache: Sync {
+    fn get(&self, key: u64) -> Option<u64>;
+    fn insert(&self, key: u64, value: u64);
+}
+
+impl SharedCache for Mutex<LRUCache<u64, u64>> {
+    fn get(&self, key: u64) -> Option<u64> {
+        self.lock().unwrap().get(&key).copied()
+    }
+
+    fn insert(&self, key: u64, value: u64) {
+        self.lock().unwrap().insert(key, value);
+    }
+}
+
+impl SharedCache for ConcurrentLRUCache<u64, u64> {
+    fn get(&self, key: u64) -> Option<u64> {
+        ConcurrentLRUCache::get(self, &key)
+    }
+
+    fn insert(&self, key: u64, value: u64) {
+        ConcurrentLRUCache::insert(self, key, value);
+    }
+}
+
+fn run(cache: &impl SharedCache, threads: usize) -> Duration {
+    let start = Instant::now();
+    thread::scope(|s| {
+        for seed in 0..threads {
+            s.spawn(move || {
+                let mut rng = StdRng::seed_from_u64(seed as u64);
+                for _ in 0..OPS_PER_THREAD {
+                    let key = rng.gen_range(0..4 * CAPACITY as u64);
+                    if rng.gen_range(0..4) == 0 {
+                        cache.insert(key, key);
+                    } else {
+                        cache.get(key);
+                    }
+                }
+            });
+        }
+    });
+    start.elapsed()
+}
+
+fn bench_concurrent(c: &mut Criterion) {
+    let mut group = c.benchmark_group("concurrent");
+    for threads in [1, 2, 4, 8] {
+        group.throughput(Throughput::Elements((threads * OPS_PER_THREAD) as u64));
+        group.bench_with_input(BenchmarkId::new("mutex", threads), &threads, |b, &n| {
+            let cache = Mutex::new(LRUCache::new(CAPACITY));
+            b.iter_custom(|iters| (0..iters).map(|_| run(&cache, n)).sum())
+        });
+        group.bench_with_input(BenchmarkId::new("sharded", threads), &threads, |b, &n| {
+            let cache = ConcurrentLRUCache::new(CAPACITY);
+            b.iter_custom(|iters| (0..iters).map(|_| run(&cache, n)).sum())
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_concurrent);
+criterion_main!(benches);
@@ -0,0 +1,144 @@
+use std::collections::{BTreeMap, HashMap};
+use std::hash::Hash;
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use lru_cache::LRUCache;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+// The previous `BTreeMap`-based implementation, kept as a baseline.
+struct BTreeLRUCache<K, V> {
+    data: HashMap<K, V>,
+    cap: usize,
+    len: usize,
+    key_to_used: BTreeMap<K, usize>,
+    keys_priory: BTreeMap<usize, K>,
+    timer: usize,
+}
+
+impl<K: Clone + Hash + Ord, V> BTreeLRUCache<K, V> {
+    fn new(capacity: usize) -> Self {
+        Self {
+            data: HashMap::new(),
+            cap: capacity,
+            len: 0,
+            timer: 0,
+            key_to_used: BTreeMap::new(),
+            keys_priory: BTreeMap::new(),
+        }
+    }
+
+    fn get(&mut self, key: &K) -> Option<&V> {
+        if let Some(v) = self.data.get(key) {
+            let time = self.key_to_used.insert(key.clone(), self.timer).unwrap();
+            self.keys_priory.remove(&time);
+            self.keys_priory.insert(self.timer, key.clone());
+            self.timer += 1;
+            return Some(v);
+        }
+
+        None
+    }
+
+    fn insert(&mut self, key: K, value: V) -> Option<V> {
+        let mut res = None;
+        if self.data.contains_key(&key) {
+            res = self.data.insert(key.clone(), value);
+        } else {
+            if self.len == self.cap {
+                let t = self.keys_priory.keys().cloned().next().unwrap();
+                let k = self.keys_priory.remove(&t).unwrap();
+                self.data.remove(&k);
+                self.key_to_used.remove(&k);
+                self.len -= 1;
+            }
+
+            self.data.insert(key.clone(), value);
+            self.len += 1;
+        }
+
+        if let Some(t) = self.key_to_used.insert(key.clone(), self.timer) {
+            self.keys_priory.remove(&t);
+        }
+
+        self.keys_priory.insert(self.timer, key);
+        self.timer += 1;
+        res
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+enum Op {
+    Get(u64),
+    Insert(u64, u64),
+}
+
+fn workload(keys: u64, len: usize) -> Vec<Op> {
+    let mut rng = StdRng::seed_from_u64(42);
+    (0..len)
+        .map(|_| {
+            let key = rng.gen_range(0..keys);
+            if rng.gen_range(0..3) == 0 {
+                Op::Insert(key, rng.gen())
+            } else {
+                Op::Get(key)
+            }
+        })
+        .collect()
+}
+
+fn bench_lru(c: &mut Criterion) {
+    let mut group = c.benchmark_group("lru");
+    for cap in [100, 10_000, 100_000] {
+        let ops = workload(cap as u64 * 4, 100_000);
+        group.bench_with_input(BenchmarkId::new("slab", cap), &ops, |b, ops| {
+            b.iter(|| {
+                let mut cache = LRUCache::new(cap);
+                for op in ops {
+                    match *op {
+                        Op::Get(key) => {
+                            cache.get(&key);
+                        }
+                        Op::Insert(key, value) => {
+                            cache.insert(key, value);
+                        }
+                    }
+                }
+            })
+        });
+        group.bench_with_input(BenchmarkId::new("slab+stats", cap), &ops, |b, ops| {
+            b.iter(|| {
+                let mut cache = LRUCache::new(cap).with_stats();
+                for op in ops {
+                    match *op {
+                        Op::Get(key) => {
+                            cache.get(&key);
+                        }
+                        Op::Insert(key, value) => {
+                            cache.insert(key, value);
+                        }
+                    }
+                }
+            })
+        });
+        group.bench_with_input(BenchmarkId::new("btree", cap), &ops, |b, ops| {
+            b.iter(|| {
+                let mut cache = BTreeLRUCache::new(cap);
+                for op in ops {
+                    match *op {
+                        Op::Get(key) => {
+                            cache.get(&key);
+                        }
+                        Op::Insert(key, value) => {
+                            cache.insert(key, value);
+                        }
+                    }
+                }
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_lru);
+criterion_main!(benches);
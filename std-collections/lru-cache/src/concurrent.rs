@@ -0,0 +1,241 @@
+#![forbid(unsafe_code)]
+
+use std::borrow::Borrow;
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::hash::{BuildHasher, Hash, RandomState};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::thread;
+
+use crate::{EvictionPolicy, LRUCache, Lru};
+
+// Keys are spread over independently locked shards, so threads touching
+// different shards never wait for each other. Recency is tracked per shard.
+#[derive(Debug)]
+pub struct ConcurrentLRUCache<K, V, P = Lru> {
+    shards: Box<[Shard<K, V, P>]>,
+    hasher: RandomState,
+}
+
+#[derive(Debug)]
+struct Shard<K, V, P> {
+    cache: Mutex<LRUCache<K, V, P>>,
+    loading: Mutex<HashMap<K, Arc<Flight<V>>>>,
+}
+
+#[derive(Debug)]
+enum State<V> {
+    Loading,
+    Loaded(V),
+    Failed,
+}
+
+// A load in progress that other threads missing on the same key wait for.
+#[derive(Debug)]
+struct Flight<V> {
+    state: Mutex<State<V>>,
+    done: Condvar,
+}
+
+impl<V: Clone> Flight<V> {
+    fn new() -> Self {
+        Self {
+            state: Mutex::new(State::Loading),
+            done: Condvar::new(),
+        }
+    }
+
+    fn wait(&self) -> Option<V> {
+        let mut state = lock(&self.state);
+        loop {
+            match &*state {
+                State::Loading => state = self.done.wait(state).unwrap(),
+                State::Loaded(value) => return Some(value.clone()),
+                State::Failed => return None,
+            }
+        }
+    }
+
+    fn finish(&self, state: State<V>) {
+        *lock(&self.state) = state;
+        self.done.notify_all();
+    }
+}
+
+// Unregisters the flight even if the loader fails or panics, so waiters can
+// retry on their own.
+struct Landing<'a, K: Hash + Eq, V: Clone> {
+    loading: &'a Mutex<HashMap<K, Arc<Flight<V>>>>,
+    key: &'a K,
+    value: Option<V>,
+}
+
+impl<K: Hash + Eq, V: Clone> Drop for Landing<'_, K, V> {
+    fn drop(&mut self) {
+        let flight = lock(self.loading).remove(self.key);
+        if let Some(flight) = flight {
+            flight.finish(self.value.take().map_or(State::Failed, State::Loaded));
+        }
+    }
+}
+
+impl<K: Hash + Eq, V> ConcurrentLRUCache<K, V> {
+    pub fn new(capacity: usize) -> Self {
+        let threads = thread::available_parallelism().map_or(1, |n| n.get());
+        Self::with_shards(capacity, (4 * threads).next_power_of_two())
+    }
+
+    pub fn with_shards(capacity: usize, shards: usize) -> Self {
+        Self::with_policy(capacity, shards, Lru::new)
+    }
+}
+
+impl<K: Hash + Eq, V, P: EvictionPolicy> ConcurrentLRUCache<K, V, P> {
+    // The capacity is split evenly, with the first shards taking the
+    // remainder, so no shard is left without room.
+    pub fn with_policy(capacity: usize, shards: usize, mut make_policy: impl FnMut() -> P) -> Self {
+        assert!(capacity > 0 && shards > 0);
+        let shards = shards.min(capacity);
+        let per_shard = capacity / shards;
+        let extra = capacity % shards;
+        Self {
+            shards: (0..shards)
+                .map(|i| Shard {
+                    cache: Mutex::new(LRUCache::with_policy(
+                        per_shard + usize::from(i < extra),
+                        make_policy(),
+                    )),
+                    loading: Mutex::new(HashMap::new()),
+                })
+                .collect(),
+            hasher: RandomState::new(),
+        }
+    }
+
+    pub fn shard_count(&self) -> usize {
+        self.shards.len()
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.shards.iter().map(|s| lock(&s.cache).capacity()).sum()
+    }
+
+    pub fn len(&self) -> usize {
+        self.shards.iter().map(|s| lock(&s.cache).len()).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.shards.iter().all(|s| lock(&s.cache).is_empty())
+    }
+
+    // Values are cloned out of the lock; wrap them in `Arc` if that is costly.
+    pub fn get<Q>(&self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+        V: Clone,
+    {
+        self.shard(key).get(key).cloned()
+    }
+
+    pub fn peek<Q>(&self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+        V: Clone,
+    {
+        self.shard(key).peek(key).cloned()
+    }
+
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.shard(key).contains_key(key)
+    }
+
+    pub fn insert(&self, key: K, value: V) -> Option<V> {
+        self.shard(&key).insert(key, value)
+    }
+
+    pub fn remove<Q>(&self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.shard(key).remove(key)
+    }
+
+    pub fn clear(&self) {
+        for shard in self.shards.iter() {
+            lock(&shard.cache).clear();
+        }
+    }
+
+    // Concurrent misses on the same key run only one of the loaders; the
+    // others wait for its value.
+    pub fn get_or_insert_with(&self, key: K, load: impl FnOnce() -> V) -> V
+    where
+        K: Clone,
+        V: Clone,
+    {
+        match self.try_get_or_insert_with(key, || Ok::<_, Infallible>(load())) {
+            Ok(value) => value,
+            Err(never) => match never {},
+        }
+    }
+
+    // Waiters retry with their own loader if the one they waited for fails.
+    pub fn try_get_or_insert_with<E>(
+        &self,
+        key: K,
+        load: impl FnOnce() -> Result<V, E>,
+    ) -> Result<V, E>
+    where
+        K: Clone,
+        V: Clone,
+    {
+        let shard = self.shard_of(&key);
+        loop {
+            // The cache is checked under `loading`, so a finished flight is
+            // either still registered or its value is already cached.
+            let mut loading = lock(&shard.loading);
+            if let Some(value) = lock(&shard.cache).get(&key) {
+                return Ok(value.clone());
+            }
+            if let Some(flight) = loading.get(&key).cloned() {
+                drop(loading);
+                match flight.wait() {
+                    Some(value) => return Ok(value),
+                    None => continue,
+                }
+            }
+            loading.insert(key.clone(), Arc::new(Flight::new()));
+            break;
+        }
+
+        let mut landing = Landing {
+            loading: &shard.loading,
+            key: &key,
+            value: None,
+        };
+        let value = load()?;
+        lock(&shard.cache).insert(key.clone(), value.clone());
+        landing.value = Some(value.clone());
+        Ok(value)
+    }
+
+    fn shard_of<Q: Hash + ?Sized>(&self, key: &Q) -> &Shard<K, V, P> {
+        let hash = self.hasher.hash_one(key);
+        &self.shards[hash as usize % self.shards.len()]
+    }
+
+    fn shard<Q: Hash + ?Sized>(&self, key: &Q) -> MutexGuard<'_, LRUCache<K, V, P>> {
+        lock(&self.shard_of(key).cache)
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap()
+}
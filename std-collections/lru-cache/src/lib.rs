@@ -1,66 +1,587 @@
 #![forbid(unsafe_code)]
 
-use std::collections::{BTreeMap, HashMap};
-use std::hash::Hash;
+use std::borrow::Borrow;
+use std::convert::Infallible;
+use std::fmt::{self, Debug};
+use std::hash::{BuildHasher, Hash, RandomState};
+use std::ops::Deref;
+use std::time::{Duration, Instant};
+
+use hashbrown::HashTable;
+
+mod clock;
+mod concurrent;
+mod list;
+mod listener;
+#[cfg(feature = "serde")]
+mod persist;
+pub mod policy;
+mod slab;
+mod stats;
+mod weigher;
+
+pub use clock::{Clock, ManualClock, SystemClock};
+pub use concurrent::ConcurrentLRUCache;
+pub use listener::{EvictionListener, EvictionReason};
+pub use policy::{AdaptiveReplacement, EvictionPolicy, Lfu, Lru, TwoQueue, WTinyLfu};
+pub use stats::CacheStats;
+pub use weigher::Weigher;
+
+use slab::Slab;
 
 #[derive(Debug)]
-pub struct LRUCache<K, V> {
-    data: HashMap<K, V>,
+struct Entry<K, V> {
+    key: K,
+    value: V,
+    hash: u64,
+    expires: Option<Instant>,
+    weight: usize,
+}
+
+// A value returned by `get_or_insert_with`. Loaded values heavier than
+// `max_weight` are handed back without being cached.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Loaded<'a, V> {
+    Cached(&'a V),
+    Uncached(V),
+}
+
+impl<V> Deref for Loaded<'_, V> {
+    type Target = V;
+
+    fn deref(&self) -> &V {
+        match self {
+            Self::Cached(value) => value,
+            Self::Uncached(value) => value,
+        }
+    }
+}
+
+pub struct LRUCache<K, V, P = Lru> {
+    index: HashTable<usize>,
+    entries: Slab<Entry<K, V>>,
+    policy: P,
+    hasher: RandomState,
     cap: usize,
-    len: usize,
-    key_to_used: BTreeMap<K, usize>,
-    keys_priory: BTreeMap<usize, K>,
-    timer: usize,
+    clock: Box<dyn Clock>,
+    default_ttl: Option<Duration>,
+    weigher: Box<dyn Weigher<K, V>>,
+    max_weight: usize,
+    total_weight: usize,
+    listener: Option<Box<dyn EvictionListener<K, V>>>,
+    stats: Option<CacheStats>,
 }
 
-impl<K: Clone + Hash + Ord, V> LRUCache<K, V> {
+impl<K: Debug, V: Debug, P: Debug> Debug for LRUCache<K, V, P> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LRUCache")
+            .field("entries", &self.entries)
+            .field("policy", &self.policy)
+            .field("cap", &self.cap)
+            .field("clock", &self.clock)
+            .field("default_ttl", &self.default_ttl)
+            .field("max_weight", &self.max_weight)
+            .field("total_weight", &self.total_weight)
+            .field("listener", &self.listener.is_some())
+            .field("stats", &self.stats)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<K: Hash + Eq, V> LRUCache<K, V> {
     pub fn new(capacity: usize) -> Self {
+        Self::with_policy(capacity, Lru::new())
+    }
+}
+
+impl<K: Hash + Eq, V, P: EvictionPolicy> LRUCache<K, V, P> {
+    pub fn with_policy(capacity: usize, mut policy: P) -> Self {
         assert!(capacity > 0);
+        policy.set_capacity(capacity);
         Self {
-            data: HashMap::new(),
+            index: HashTable::new(),
+            entries: Slab::new(),
+            policy,
+            hasher: RandomState::new(),
             cap: capacity,
-            len: 0,
-            timer: 0,
-            key_to_used: BTreeMap::new(),
-            keys_priory: BTreeMap::new(),
+            clock: Box::new(SystemClock),
+            default_ttl: None,
+            weigher: Box::new(weigher::Unit),
+            max_weight: usize::MAX,
+            total_weight: 0,
+            listener: None,
+            stats: None,
         }
     }
 
-    pub fn get(&mut self, key: &K) -> Option<&V> {
-        if let Some(v) = self.data.get(key) {
-            let time = self.key_to_used.insert(key.clone(), self.timer).unwrap();
-            self.keys_priory.remove(&time);
-            self.keys_priory.insert(self.timer, key.clone());
-            self.timer += 1;
-            return Some(v);
+    // Without this, no counters are kept at all.
+    pub fn with_stats(mut self) -> Self {
+        self.stats = Some(CacheStats::default());
+        self
+    }
+
+    pub fn stats(&self) -> Option<&CacheStats> {
+        self.stats.as_ref()
+    }
+
+    pub fn reset_stats(&mut self) {
+        if let Some(stats) = &mut self.stats {
+            *stats = CacheStats::default();
         }
+    }
 
-        None
+    // Values handed back to the caller, as by `insert`, `remove` or
+    // `pop_lru`, are not reported to the listener.
+    pub fn with_eviction_listener(
+        mut self,
+        listener: impl EvictionListener<K, V> + 'static,
+    ) -> Self {
+        self.listener = Some(Box::new(listener));
+        self
     }
 
-    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
-        let mut res = None;
-        if self.data.contains_key(&key) {
-            res = self.data.insert(key.clone(), value);
+    // Limits the total weight of the entries on top of their count.
+    pub fn with_max_weight(
+        mut self,
+        max_weight: usize,
+        weigher: impl Weigher<K, V> + 'static,
+    ) -> Self {
+        assert!(max_weight > 0);
+        self.weigher = Box::new(weigher);
+        self.max_weight = max_weight;
+
+        let order: Vec<_> = self.policy.order().collect();
+        self.total_weight = 0;
+        for idx in order {
+            let entry = self.entries.get_mut(idx);
+            entry.weight = self.weigher.weigh(&entry.key, &entry.value);
+            self.total_weight += entry.weight;
+        }
+        self.shrink();
+        self
+    }
+
+    pub fn max_weight(&self) -> usize {
+        self.max_weight
+    }
+
+    pub fn total_weight(&self) -> usize {
+        self.total_weight
+    }
+
+    pub fn with_clock(mut self, clock: impl Clock + 'static) -> Self {
+        self.clock = Box::new(clock);
+        self
+    }
+
+    pub fn with_default_ttl(mut self, ttl: Duration) -> Self {
+        self.default_ttl = Some(ttl);
+        self
+    }
+
+    pub fn default_ttl(&self) -> Option<Duration> {
+        self.default_ttl
+    }
+
+    pub fn set_default_ttl(&mut self, ttl: Option<Duration>) {
+        self.default_ttl = ttl;
+    }
+
+    pub fn policy(&self) -> &P {
+        &self.policy
+    }
+
+    // Expired entries are counted until they are looked up or purged.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.cap
+    }
+
+    pub fn get<Q>(&mut self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let idx = self.access(key)?;
+        Some(&self.entries.get(idx).value)
+    }
+
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let idx = self.access(key)?;
+        Some(&mut self.entries.get_mut(idx).value)
+    }
+
+    pub fn peek<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let idx = self.find(self.hasher.hash_one(key), key)?;
+        let entry = self.entries.get(idx);
+        (!self.is_expired(entry, None)).then_some(&entry.value)
+    }
+
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.peek(key).is_some()
+    }
+
+    // The entry the policy would evict next. If that one has expired, the
+    // least valuable live entry is returned instead.
+    pub fn peek_lru(&self) -> Option<(&K, &V)> {
+        let now = self.clock.now();
+        let live = |&idx: &usize| !self.is_expired(self.entries.get(idx), Some(now));
+        let idx = match self.policy.victim() {
+            Some(idx) if live(&idx) => idx,
+            _ => self.policy.order().filter(live).last()?,
+        };
+        let entry = self.entries.get(idx);
+        Some((&entry.key, &entry.value))
+    }
+
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let idx = self.find(self.hasher.hash_one(key), key)?;
+        if self.is_expired(self.entries.get(idx), None) {
+            self.discard(idx, EvictionReason::Expired);
+            return None;
+        }
+        self.policy.on_remove(idx);
+        Some(self.remove_at(idx).value)
+    }
+
+    // Like `remove`, but the value goes to the eviction listener.
+    pub fn invalidate<Q>(&mut self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let Some(idx) = self.find(self.hasher.hash_one(key), key) else {
+            return false;
+        };
+        let expired = self.is_expired(self.entries.get(idx), None);
+        let reason = if expired {
+            EvictionReason::Expired
         } else {
-            if self.len == self.cap {
-                let t = self.keys_priory.keys().cloned().next().unwrap();
-                let k = self.keys_priory.remove(&t).unwrap();
-                self.data.remove(&k);
-                self.key_to_used.remove(&k);
-                self.len -= 1;
+            EvictionReason::Explicit
+        };
+        self.discard(idx, reason);
+        !expired
+    }
+
+    // Removes all expired entries and returns how many were dropped.
+    pub fn purge_expired(&mut self) -> usize {
+        let now = self.clock.now();
+        let expired: Vec<_> = self
+            .policy
+            .order()
+            .filter(|&idx| self.is_expired(self.entries.get(idx), Some(now)))
+            .collect();
+        for &idx in &expired {
+            self.discard(idx, EvictionReason::Expired);
+        }
+        expired.len()
+    }
+
+    // Expired victims are discarded on the way.
+    pub fn pop_lru(&mut self) -> Option<(K, V)> {
+        loop {
+            let idx = self.policy.evict()?;
+            let entry = self.remove_at(idx);
+            if !self.is_expired(&entry, None) {
+                return Some((entry.key, entry.value));
             }
+            self.notify(entry.key, entry.value, EvictionReason::Expired);
+        }
+    }
 
-            self.data.insert(key.clone(), value);
-            self.len += 1;
+    pub fn clear(&mut self) {
+        if self.listener.is_some() || self.stats.is_some() {
+            let order: Vec<_> = self.policy.order().collect();
+            for idx in order {
+                self.discard(idx, EvictionReason::Explicit);
+            }
         }
+        self.index.clear();
+        self.entries.clear();
+        self.policy.clear();
+        self.total_weight = 0;
+    }
+
+    pub fn resize(&mut self, capacity: usize) {
+        assert!(capacity > 0);
+        self.cap = capacity;
+        self.policy.set_capacity(capacity);
+        self.shrink();
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        let now = self.clock.now();
+        self.policy
+            .order()
+            .map(|idx| self.entries.get(idx))
+            .filter(move |entry| !self.is_expired(entry, Some(now)))
+            .map(|entry| (&entry.key, &entry.value))
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (&K, &mut V)> {
+        let now = self.clock.now();
+        let order: Vec<_> = self
+            .policy
+            .order()
+            .filter(|&idx| !self.is_expired(self.entries.get(idx), Some(now)))
+            .collect();
+        self.entries
+            .get_many_mut(order)
+            .into_iter()
+            .map(|entry| (&entry.key, &mut entry.value))
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &K> {
+        self.iter().map(|(key, _)| key)
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = &V> {
+        self.iter().map(|(_, value)| value)
+    }
+
+    // Entries heavier than `max_weight` are dropped, and the old value of
+    // their key is removed and returned; see `try_insert`.
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        self.insert_or_remove(key, value, self.default_ttl)
+    }
+
+    // Like `insert`, but a replaced value goes to the eviction listener.
+    pub fn put(&mut self, key: K, value: V) {
+        let replaced = match self.insert_entry(key, value, self.default_ttl) {
+            Ok(replaced) => replaced,
+            Err((key, _)) => self.remove(&key).map(|old| (key, old)),
+        };
+        if let Some((key, old)) = replaced {
+            self.notify(key, old, EvictionReason::Replaced);
+        }
+    }
+
+    pub fn get_or_insert_with(&mut self, key: K, load: impl FnOnce() -> V) -> Loaded<'_, V> {
+        match self.try_get_or_insert_with(key, || Ok::<_, Infallible>(load())) {
+            Ok(value) => value,
+            Err(never) => match never {},
+        }
+    }
+
+    pub fn try_get_or_insert_with<E>(
+        &mut self,
+        key: K,
+        load: impl FnOnce() -> Result<V, E>,
+    ) -> Result<Loaded<'_, V>, E> {
+        if let Some(idx) = self.access(&key) {
+            return Ok(Loaded::Cached(&self.entries.get(idx).value));
+        }
+
+        let value = load()?;
+        let weight = self.weigher.weigh(&key, &value);
+        if weight > self.max_weight {
+            return Ok(Loaded::Uncached(value));
+        }
+        let hash = self.hasher.hash_one(&key);
+        let expires = self.expires_at(self.default_ttl);
+        let idx = self.insert_new(key, value, hash, weight, expires);
+        Ok(Loaded::Cached(&self.entries.get(idx).value))
+    }
+
+    pub fn insert_with_ttl(&mut self, key: K, value: V, ttl: Duration) -> Option<V> {
+        self.insert_or_remove(key, value, Some(ttl))
+    }
+
+    // Hands the pair back if it can never fit, leaving the cache untouched.
+    pub fn try_insert(&mut self, key: K, value: V) -> Result<Option<V>, (K, V)> {
+        self.insert_expiring(key, value, self.default_ttl)
+    }
+
+    fn insert_or_remove(&mut self, key: K, value: V, ttl: Option<Duration>) -> Option<V> {
+        match self.insert_expiring(key, value, ttl) {
+            Ok(old) => old,
+            Err((key, _)) => self.remove(&key),
+        }
+    }
+
+    fn insert_expiring(
+        &mut self,
+        key: K,
+        value: V,
+        ttl: Option<Duration>,
+    ) -> Result<Option<V>, (K, V)> {
+        self.insert_entry(key, value, ttl)
+            .map(|replaced| replaced.map(|(_, old)| old))
+    }
 
-        if let Some(t) = self.key_to_used.insert(key.clone(), self.timer) {
-            self.keys_priory.remove(&t);
+    // On replacement the passed key is returned along with the old value.
+    fn insert_entry(
+        &mut self,
+        key: K,
+        value: V,
+        ttl: Option<Duration>,
+    ) -> Result<Option<(K, V)>, (K, V)> {
+        let weight = self.weigher.weigh(&key, &value);
+        if weight > self.max_weight {
+            return Err((key, value));
         }
 
-        self.keys_priory.insert(self.timer, key);
-        self.timer += 1;
-        res
+        let hash = self.hasher.hash_one(&key);
+        let expires = self.expires_at(ttl);
+        if let Some(idx) = self.find(hash, &key) {
+            if self.is_expired(self.entries.get(idx), None) {
+                self.discard(idx, EvictionReason::Expired);
+            } else {
+                self.policy.on_access(idx, hash);
+                let entry = self.entries.get_mut(idx);
+                entry.expires = expires;
+                self.total_weight = self.total_weight - entry.weight + weight;
+                entry.weight = weight;
+                let old = std::mem::replace(&mut entry.value, value);
+                self.record(CacheStats::update);
+                self.shrink();
+                return Ok(Some((key, old)));
+            }
+        }
+
+        self.insert_new(key, value, hash, weight, expires);
+        Ok(None)
+    }
+
+    fn insert_new(
+        &mut self,
+        key: K,
+        value: V,
+        hash: u64,
+        weight: usize,
+        expires: Option<Instant>,
+    ) -> usize {
+        while self.entries.len() == self.cap || self.total_weight + weight > self.max_weight {
+            self.evict();
+        }
+
+        self.total_weight += weight;
+        let idx = self.entries.insert(Entry {
+            key,
+            value,
+            hash,
+            expires,
+            weight,
+        });
+        let entries = &self.entries;
+        self.index
+            .insert_unique(hash, idx, |&i| entries.get(i).hash);
+        self.policy.on_insert(idx, hash);
+        let len = self.entries.len();
+        self.record(|stats| stats.insert(len));
+        idx
+    }
+
+    fn shrink(&mut self) {
+        while self.entries.len() > self.cap || self.total_weight > self.max_weight {
+            self.evict();
+        }
+    }
+
+    fn evict(&mut self) {
+        let idx = self
+            .policy
+            .evict()
+            .expect("eviction policy has no victim while entries are resident");
+        let entry = self.remove_at(idx);
+        self.notify(entry.key, entry.value, EvictionReason::Capacity);
+    }
+
+    fn discard(&mut self, idx: usize, reason: EvictionReason) {
+        self.policy.on_remove(idx);
+        let entry = self.remove_at(idx);
+        self.notify(entry.key, entry.value, reason);
+    }
+
+    fn notify(&mut self, key: K, value: V, reason: EvictionReason) {
+        self.record(|stats| stats.evict(reason));
+        if let Some(listener) = &mut self.listener {
+            listener.on_evict(key, value, reason);
+        }
+    }
+
+    fn access<Q>(&mut self, key: &Q) -> Option<usize>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let hash = self.hasher.hash_one(key);
+        match self.find(hash, key) {
+            Some(idx) if !self.is_expired(self.entries.get(idx), None) => {
+                self.record(CacheStats::hit);
+                self.policy.on_access(idx, hash);
+                Some(idx)
+            }
+            found => {
+                self.record(CacheStats::miss);
+                if let Some(idx) = found {
+                    self.discard(idx, EvictionReason::Expired);
+                }
+                self.policy.on_miss(hash);
+                None
+            }
+        }
+    }
+
+    fn record(&mut self, event: impl FnOnce(&mut CacheStats)) {
+        if let Some(stats) = &mut self.stats {
+            event(stats);
+        }
+    }
+
+    // A TTL too large to represent means the entry never expires.
+    fn expires_at(&self, ttl: Option<Duration>) -> Option<Instant> {
+        ttl.and_then(|ttl| self.clock.now().checked_add(ttl))
+    }
+
+    // Reads the clock only for entries that can expire.
+    fn is_expired(&self, entry: &Entry<K, V>, now: Option<Instant>) -> bool {
+        entry
+            .expires
+            .is_some_and(|at| now.unwrap_or_else(|| self.clock.now()) >= at)
+    }
+
+    fn find<Q>(&self, hash: u64, key: &Q) -> Option<usize>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.index
+            .find(hash, |&i| self.entries.get(i).key.borrow() == key)
+            .copied()
+    }
+
+    // Drops the entry from the storage; the policy must already forget it.
+    fn remove_at(&mut self, idx: usize) -> Entry<K, V> {
+        let hash = self.entries.get(idx).hash;
+        if let Ok(slot) = self.index.find_entry(hash, |&i| i == idx) {
+            slot.remove();
+        }
+        let entry = self.entries.remove(idx);
+        self.total_weight -= entry.weight;
+        entry
     }
 }
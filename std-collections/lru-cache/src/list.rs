@@ -0,0 +1,125 @@
+#![forbid(unsafe_code)]
+
+pub const NIL: usize = usize::MAX;
+
+#[derive(Debug, Clone, Copy)]
+struct Link {
+    prev: usize,
+    next: usize,
+    list: usize,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Ends {
+    head: usize,
+    tail: usize,
+    len: usize,
+}
+
+const DETACHED: Link = Link {
+    prev: NIL,
+    next: NIL,
+    list: NIL,
+};
+
+const EMPTY: Ends = Ends {
+    head: NIL,
+    tail: NIL,
+    len: 0,
+};
+
+// Several intrusive doubly linked lists over the same index space: every
+// index belongs to at most one list at a time and can move between lists
+// in O(1).
+#[derive(Debug)]
+pub struct Lists {
+    links: Vec<Link>,
+    ends: Vec<Ends>,
+}
+
+impl Lists {
+    pub fn new(lists: usize) -> Self {
+        Self {
+            links: Vec::new(),
+            ends: vec![EMPTY; lists],
+        }
+    }
+
+    pub fn add_list(&mut self) -> usize {
+        self.ends.push(EMPTY);
+        self.ends.len() - 1
+    }
+
+    pub fn len(&self, list: usize) -> usize {
+        self.ends[list].len
+    }
+
+    pub fn back(&self, list: usize) -> Option<usize> {
+        let tail = self.ends[list].tail;
+        (tail != NIL).then_some(tail)
+    }
+
+    pub fn list_of(&self, idx: usize) -> Option<usize> {
+        let list = self.links.get(idx)?.list;
+        (list != NIL).then_some(list)
+    }
+
+    pub fn push_front(&mut self, list: usize, idx: usize) {
+        if idx >= self.links.len() {
+            self.links.resize(idx + 1, DETACHED);
+        }
+
+        let head = self.ends[list].head;
+        self.links[idx] = Link {
+            prev: NIL,
+            next: head,
+            list,
+        };
+        match head {
+            NIL => self.ends[list].tail = idx,
+            head => self.links[head].prev = idx,
+        }
+        self.ends[list].head = idx;
+        self.ends[list].len += 1;
+    }
+
+    pub fn remove(&mut self, idx: usize) -> Option<usize> {
+        let Link { prev, next, list } = *self.links.get(idx)?;
+        if list == NIL {
+            return None;
+        }
+
+        match prev {
+            NIL => self.ends[list].head = next,
+            prev => self.links[prev].next = next,
+        }
+        match next {
+            NIL => self.ends[list].tail = prev,
+            next => self.links[next].prev = prev,
+        }
+        self.ends[list].len -= 1;
+        self.links[idx] = DETACHED;
+        Some(list)
+    }
+
+    pub fn move_to_front(&mut self, list: usize, idx: usize) {
+        self.remove(idx);
+        self.push_front(list, idx);
+    }
+
+    pub fn iter(&self, list: usize) -> impl Iterator<Item = usize> + '_ {
+        let mut curr = self.ends[list].head;
+        std::iter::from_fn(move || {
+            let idx = curr;
+            (idx != NIL).then(|| {
+                curr = self.links[idx].next;
+                idx
+            })
+        })
+    }
+
+    pub fn clear(&mut self) {
+        self.links.clear();
+        self.ends.fill(EMPTY);
+    }
+}
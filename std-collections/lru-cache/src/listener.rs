@@ -0,0 +1,25 @@
+#![forbid(unsafe_code)]
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EvictionReason {
+    // Made room for another entry or the cache shrank.
+    Capacity,
+    Expired,
+    // The value was overwritten by `put`.
+    Replaced,
+    // Dropped by `invalidate` or `clear`.
+    Explicit,
+}
+
+pub trait EvictionListener<K, V>: Send + Sync {
+    fn on_evict(&mut self, key: K, value: V, reason: EvictionReason);
+}
+
+impl<K, V, F> EvictionListener<K, V> for F
+where
+    F: FnMut(K, V, EvictionReason) + Send + Sync,
+{
+    fn on_evict(&mut self, key: K, value: V, reason: EvictionReason) {
+        self(key, value, reason)
+    }
+}
@@ -0,0 +1,81 @@
+#![forbid(unsafe_code)]
+
+use std::fmt;
+use std::hash::Hash;
+use std::io::{Read, Write};
+use std::marker::PhantomData;
+
+use serde::de::{DeserializeSeed, IgnoredAny, SeqAccess, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::{EvictionPolicy, LRUCache};
+
+// Entries are written as a JSON array of `[key, value]` pairs from the most
+// to the least recently used one. Expiration times are not kept.
+impl<K, V, P> LRUCache<K, V, P>
+where
+    K: Hash + Eq + Serialize,
+    V: Serialize,
+    P: EvictionPolicy,
+{
+    pub fn save<W: Write>(&self, writer: W) -> serde_json::Result<()> {
+        let mut serializer = serde_json::Serializer::new(writer);
+        serializer.collect_seq(self.iter())
+    }
+}
+
+impl<K, V> LRUCache<K, V>
+where
+    K: Hash + Eq + for<'de> Deserialize<'de>,
+    V: for<'de> Deserialize<'de>,
+{
+    // Keeps the `capacity` most recent entries and skips the rest.
+    pub fn load<R: Read>(reader: R, capacity: usize) -> serde_json::Result<Self> {
+        let mut deserializer = serde_json::Deserializer::from_reader(reader);
+        let mut entries = Recent {
+            capacity,
+            marker: PhantomData,
+        }
+        .deserialize(&mut deserializer)?;
+        deserializer.end()?;
+
+        let mut cache = Self::new(capacity);
+        while let Some((key, value)) = entries.pop() {
+            cache.insert(key, value);
+        }
+        Ok(cache)
+    }
+}
+
+struct Recent<K, V> {
+    capacity: usize,
+    marker: PhantomData<(K, V)>,
+}
+
+impl<'de, K: Deserialize<'de>, V: Deserialize<'de>> DeserializeSeed<'de> for Recent<K, V> {
+    type Value = Vec<(K, V)>;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+impl<'de, K: Deserialize<'de>, V: Deserialize<'de>> Visitor<'de> for Recent<K, V> {
+    type Value = Vec<(K, V)>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a sequence of key-value pairs")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut entries = Vec::new();
+        while entries.len() < self.capacity {
+            match seq.next_element()? {
+                Some(entry) => entries.push(entry),
+                None => return Ok(entries),
+            }
+        }
+        while seq.next_element::<IgnoredAny>()?.is_some() {}
+        Ok(entries)
+    }
+}
@@ -0,0 +1,118 @@
+#![forbid(unsafe_code)]
+
+use crate::list::Lists;
+
+use super::{EvictionPolicy, Ghosts};
+
+const RECENT: usize = 0;
+const FREQUENT: usize = 1;
+
+// ARC keeps entries seen once (`T1`) and at least twice (`T2`) in separate
+// LRU lists and adapts the target size of `T1` using hits in the ghost lists
+// of keys recently evicted from each of them.
+#[derive(Debug)]
+pub struct AdaptiveReplacement {
+    lists: Lists,
+    hashes: Vec<u64>,
+    recent_ghosts: Ghosts,
+    frequent_ghosts: Ghosts,
+    target: usize,
+    cap: usize,
+}
+
+impl Default for AdaptiveReplacement {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AdaptiveReplacement {
+    pub fn new() -> Self {
+        Self {
+            lists: Lists::new(2),
+            hashes: Vec::new(),
+            recent_ghosts: Ghosts::default(),
+            frequent_ghosts: Ghosts::default(),
+            target: 0,
+            cap: 1,
+        }
+    }
+
+    pub fn target(&self) -> usize {
+        self.target
+    }
+}
+
+impl EvictionPolicy for AdaptiveReplacement {
+    fn set_capacity(&mut self, capacity: usize) {
+        self.cap = capacity;
+        self.target = self.target.min(capacity);
+        self.recent_ghosts.truncate(capacity);
+        self.frequent_ghosts.truncate(capacity);
+    }
+
+    fn on_insert(&mut self, idx: usize, hash: u64) {
+        if idx >= self.hashes.len() {
+            self.hashes.resize(idx + 1, 0);
+        }
+        self.hashes[idx] = hash;
+
+        if self.recent_ghosts.remove(hash) {
+            let delta = (self.frequent_ghosts.len() / (self.recent_ghosts.len() + 1)).max(1);
+            self.target = (self.target + delta).min(self.cap);
+            self.lists.push_front(FREQUENT, idx);
+        } else if self.frequent_ghosts.remove(hash) {
+            let delta = (self.recent_ghosts.len() / (self.frequent_ghosts.len() + 1)).max(1);
+            self.target = self.target.saturating_sub(delta);
+            self.lists.push_front(FREQUENT, idx);
+        } else {
+            self.lists.push_front(RECENT, idx);
+        }
+    }
+
+    fn on_access(&mut self, idx: usize, _hash: u64) {
+        self.lists.move_to_front(FREQUENT, idx);
+    }
+
+    fn on_remove(&mut self, idx: usize) {
+        self.lists.remove(idx);
+    }
+
+    fn victim(&self) -> Option<usize> {
+        let recent = self.lists.len(RECENT);
+        if recent > 0 && (recent > self.target || self.lists.len(FREQUENT) == 0) {
+            self.lists.back(RECENT)
+        } else {
+            self.lists.back(FREQUENT)
+        }
+    }
+
+    fn evict(&mut self) -> Option<usize> {
+        let idx = self.victim()?;
+        let hash = self.hashes[idx];
+        match self.lists.remove(idx) {
+            Some(RECENT) => {
+                self.recent_ghosts.push(hash);
+                self.recent_ghosts
+                    .truncate(self.cap.saturating_sub(self.lists.len(RECENT)));
+            }
+            _ => {
+                self.frequent_ghosts.push(hash);
+                self.frequent_ghosts
+                    .truncate(self.cap.saturating_sub(self.lists.len(FREQUENT)));
+            }
+        }
+        Some(idx)
+    }
+
+    fn order(&self) -> Box<dyn Iterator<Item = usize> + '_> {
+        Box::new(self.lists.iter(FREQUENT).chain(self.lists.iter(RECENT)))
+    }
+
+    fn clear(&mut self) {
+        self.lists.clear();
+        self.recent_ghosts.clear();
+        self.frequent_ghosts.clear();
+        self.target = 0;
+    }
+}
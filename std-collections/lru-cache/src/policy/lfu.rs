@@ -0,0 +1,147 @@
+#![forbid(unsafe_code)]
+
+use crate::list::{Lists, NIL};
+
+use super::EvictionPolicy;
+
+#[derive(Debug, Clone, Copy)]
+struct Bucket {
+    freq: usize,
+    prev: usize,
+    next: usize,
+}
+
+// Entries with the same access count share a bucket; buckets form a list
+// ordered by count, so the victim is the least recently used entry of the
+// first bucket.
+#[derive(Debug)]
+pub struct Lfu {
+    entries: Lists,
+    buckets: Vec<Bucket>,
+    free: Vec<usize>,
+    first: usize,
+    last: usize,
+}
+
+impl Default for Lfu {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Lfu {
+    pub fn new() -> Self {
+        Self {
+            entries: Lists::new(0),
+            buckets: Vec::new(),
+            free: Vec::new(),
+            first: NIL,
+            last: NIL,
+        }
+    }
+
+    pub fn frequency(&self, idx: usize) -> Option<usize> {
+        self.entries.list_of(idx).map(|b| self.buckets[b].freq)
+    }
+
+    fn bucket_after(&mut self, prev: usize, freq: usize) -> usize {
+        let next = match prev {
+            NIL => self.first,
+            prev => self.buckets[prev].next,
+        };
+        if next != NIL && self.buckets[next].freq == freq {
+            return next;
+        }
+
+        let bucket = Bucket { freq, prev, next };
+        let id = match self.free.pop() {
+            Some(id) => {
+                self.buckets[id] = bucket;
+                id
+            }
+            None => {
+                self.buckets.push(bucket);
+                self.entries.add_list()
+            }
+        };
+
+        match prev {
+            NIL => self.first = id,
+            prev => self.buckets[prev].next = id,
+        }
+        match next {
+            NIL => self.last = id,
+            next => self.buckets[next].prev = id,
+        }
+        id
+    }
+
+    fn release_if_empty(&mut self, id: usize) {
+        if self.entries.len(id) > 0 {
+            return;
+        }
+
+        let Bucket { prev, next, .. } = self.buckets[id];
+        match prev {
+            NIL => self.first = next,
+            prev => self.buckets[prev].next = next,
+        }
+        match next {
+            NIL => self.last = prev,
+            next => self.buckets[next].prev = prev,
+        }
+        self.free.push(id);
+    }
+}
+
+impl EvictionPolicy for Lfu {
+    fn set_capacity(&mut self, _capacity: usize) {}
+
+    fn on_insert(&mut self, idx: usize, _hash: u64) {
+        let bucket = self.bucket_after(NIL, 1);
+        self.entries.push_front(bucket, idx);
+    }
+
+    fn on_access(&mut self, idx: usize, _hash: u64) {
+        let Some(curr) = self.entries.list_of(idx) else {
+            return;
+        };
+
+        let next = self.bucket_after(curr, self.buckets[curr].freq + 1);
+        self.entries.move_to_front(next, idx);
+        self.release_if_empty(curr);
+    }
+
+    fn on_remove(&mut self, idx: usize) {
+        if let Some(bucket) = self.entries.remove(idx) {
+            self.release_if_empty(bucket);
+        }
+    }
+
+    fn victim(&self) -> Option<usize> {
+        match self.first {
+            NIL => None,
+            first => self.entries.back(first),
+        }
+    }
+
+    fn order(&self) -> Box<dyn Iterator<Item = usize> + '_> {
+        let mut bucket = self.last;
+        let buckets = std::iter::from_fn(move || {
+            let id = bucket;
+            (id != NIL).then(|| {
+                bucket = self.buckets[id].prev;
+                id
+            })
+        });
+        Box::new(buckets.flat_map(|id| self.entries.iter(id)))
+    }
+
+    fn clear(&mut self) {
+        self.entries = Lists::new(0);
+        self.buckets.clear();
+        self.free.clear();
+        self.first = NIL;
+        self.last = NIL;
+    }
+}
@@ -0,0 +1,145 @@
+#![forbid(unsafe_code)]
+
+use std::collections::{HashMap, VecDeque};
+
+use crate::list::Lists;
+
+mod arc;
+mod lfu;
+mod tiny_lfu;
+mod two_queue;
+
+pub use arc::AdaptiveReplacement;
+pub use lfu::Lfu;
+pub use tiny_lfu::WTinyLfu;
+pub use two_queue::TwoQueue;
+
+// Entries are identified by their slot index in the cache storage and by the
+// hash of their key, which policies may use to remember evicted keys.
+pub trait EvictionPolicy {
+    fn set_capacity(&mut self, capacity: usize);
+
+    fn on_insert(&mut self, idx: usize, hash: u64);
+
+    fn on_access(&mut self, idx: usize, hash: u64);
+
+    fn on_miss(&mut self, _hash: u64) {}
+
+    fn on_remove(&mut self, idx: usize);
+
+    // `victim` and `evict` must return `Some` whenever any entry is
+    // resident, or the cache panics when it needs room.
+    fn victim(&self) -> Option<usize>;
+
+    fn evict(&mut self) -> Option<usize> {
+        let idx = self.victim()?;
+        self.on_remove(idx);
+        Some(idx)
+    }
+
+    // Resident entries from the most to the least valuable one.
+    fn order(&self) -> Box<dyn Iterator<Item = usize> + '_>;
+
+    fn clear(&mut self);
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug)]
+pub struct Lru {
+    lists: Lists,
+}
+
+impl Default for Lru {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Lru {
+    pub fn new() -> Self {
+        Self {
+            lists: Lists::new(1),
+        }
+    }
+}
+
+impl EvictionPolicy for Lru {
+    fn set_capacity(&mut self, _capacity: usize) {}
+
+    fn on_insert(&mut self, idx: usize, _hash: u64) {
+        self.lists.push_front(0, idx);
+    }
+
+    fn on_access(&mut self, idx: usize, _hash: u64) {
+        self.lists.move_to_front(0, idx);
+    }
+
+    fn on_remove(&mut self, idx: usize) {
+        self.lists.remove(idx);
+    }
+
+    fn victim(&self) -> Option<usize> {
+        self.lists.back(0)
+    }
+
+    fn order(&self) -> Box<dyn Iterator<Item = usize> + '_> {
+        Box::new(self.lists.iter(0))
+    }
+
+    fn clear(&mut self) {
+        self.lists.clear();
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+// Hashes of recently evicted keys in FIFO order. Removed hashes stay in the
+// queue until they reach its front and are skipped there.
+#[derive(Debug, Default)]
+struct Ghosts {
+    queue: VecDeque<(u64, u64)>,
+    live: HashMap<u64, u64>,
+    seq: u64,
+}
+
+impl Ghosts {
+    fn len(&self) -> usize {
+        self.live.len()
+    }
+
+    fn push(&mut self, hash: u64) {
+        self.seq += 1;
+        self.live.insert(hash, self.seq);
+        self.queue.push_back((hash, self.seq));
+
+        if self.queue.len() > 2 * self.live.len() + 16 {
+            let live = &self.live;
+            self.queue.retain(|(hash, seq)| live.get(hash) == Some(seq));
+        }
+    }
+
+    fn remove(&mut self, hash: u64) -> bool {
+        self.live.remove(&hash).is_some()
+    }
+
+    fn pop_oldest(&mut self) {
+        while let Some((hash, seq)) = self.queue.pop_front() {
+            if self.live.get(&hash) == Some(&seq) {
+                self.live.remove(&hash);
+                return;
+            }
+        }
+    }
+
+    fn truncate(&mut self, len: usize) {
+        while self.len() > len {
+            self.pop_oldest();
+        }
+    }
+
+    fn clear(&mut self) {
+        self.queue.clear();
+        self.live.clear();
+    }
+}
@@ -0,0 +1,208 @@
+#![forbid(unsafe_code)]
+
+use crate::list::Lists;
+
+use super::EvictionPolicy;
+
+const WINDOW: usize = 0;
+const PROBATION: usize = 1;
+const PROTECTED: usize = 2;
+
+const DEPTH: usize = 4;
+const MAX_COUNT: u8 = 15;
+const SEEDS: [u64; DEPTH] = [
+    0x9e37_79b9_7f4a_7c15,
+    0xc2b2_ae3d_27d4_eb4f,
+    0x1656_67b1_9e37_79f9,
+    0x27d4_eb2f_1656_67c5,
+];
+
+// Approximate access counts with periodic halving, so the estimate follows
+// the recent popularity of a key.
+#[derive(Debug)]
+pub struct CountMinSketch {
+    counters: Vec<u8>,
+    mask: usize,
+    additions: usize,
+    sample: usize,
+}
+
+impl CountMinSketch {
+    pub fn new(capacity: usize) -> Self {
+        let width = capacity.max(16).next_power_of_two();
+        Self {
+            counters: vec![0; width * DEPTH],
+            mask: width - 1,
+            additions: 0,
+            sample: 10 * width,
+        }
+    }
+
+    fn slot(&self, row: usize, hash: u64) -> usize {
+        let mixed = (hash ^ SEEDS[row]).wrapping_mul(SEEDS[(row + 1) % DEPTH]);
+        row * (self.mask + 1) + ((mixed >> 32) as usize & self.mask)
+    }
+
+    pub fn estimate(&self, hash: u64) -> u8 {
+        (0..DEPTH)
+            .map(|row| self.counters[self.slot(row, hash)])
+            .min()
+            .unwrap()
+    }
+
+    pub fn increment(&mut self, hash: u64) {
+        let min = self.estimate(hash);
+        if min == MAX_COUNT {
+            return;
+        }
+
+        for row in 0..DEPTH {
+            let slot = self.slot(row, hash);
+            if self.counters[slot] == min {
+                self.counters[slot] += 1;
+            }
+        }
+
+        self.additions += 1;
+        if self.additions >= self.sample {
+            self.counters.iter_mut().for_each(|c| *c /= 2);
+            self.additions /= 2;
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.counters.fill(0);
+        self.additions = 0;
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+// W-TinyLFU: new entries go through a small LRU window; when the window
+// overflows its oldest entry competes with the victim of the segmented LRU
+// main area and only the one with the higher estimated frequency survives.
+#[derive(Debug)]
+pub struct WTinyLfu {
+    lists: Lists,
+    hashes: Vec<u64>,
+    sketch: CountMinSketch,
+    window_cap: usize,
+    protected_cap: usize,
+}
+
+impl Default for WTinyLfu {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl WTinyLfu {
+    pub fn new() -> Self {
+        Self {
+            lists: Lists::new(3),
+            hashes: Vec::new(),
+            sketch: CountMinSketch::new(0),
+            window_cap: 1,
+            protected_cap: 1,
+        }
+    }
+
+    fn main_victim(&self) -> Option<usize> {
+        self.lists
+            .back(PROBATION)
+            .or_else(|| self.lists.back(PROTECTED))
+    }
+
+    fn choose(&self) -> Option<(usize, Option<usize>)> {
+        let Some(candidate) = self.lists.back(WINDOW) else {
+            return self.main_victim().map(|victim| (victim, None));
+        };
+        if self.lists.len(WINDOW) < self.window_cap {
+            if let Some(victim) = self.main_victim() {
+                return Some((victim, None));
+            }
+        }
+
+        match self.main_victim() {
+            Some(victim)
+                if self.sketch.estimate(self.hashes[candidate])
+                    > self.sketch.estimate(self.hashes[victim]) =>
+            {
+                Some((victim, Some(candidate)))
+            }
+            _ => Some((candidate, None)),
+        }
+    }
+}
+
+impl EvictionPolicy for WTinyLfu {
+    fn set_capacity(&mut self, capacity: usize) {
+        self.window_cap = (capacity / 100).max(1);
+        self.protected_cap = ((capacity - self.window_cap.min(capacity)) * 4 / 5).max(1);
+        self.sketch = CountMinSketch::new(capacity);
+    }
+
+    fn on_insert(&mut self, idx: usize, hash: u64) {
+        if idx >= self.hashes.len() {
+            self.hashes.resize(idx + 1, 0);
+        }
+        self.hashes[idx] = hash;
+        self.sketch.increment(hash);
+        self.lists.push_front(WINDOW, idx);
+
+        // While the cache has room, entries leave the window without a contest.
+        if self.lists.len(WINDOW) > self.window_cap {
+            let oldest = self.lists.back(WINDOW).unwrap();
+            self.lists.move_to_front(PROBATION, oldest);
+        }
+    }
+
+    fn on_access(&mut self, idx: usize, hash: u64) {
+        self.sketch.increment(hash);
+        match self.lists.list_of(idx) {
+            Some(PROBATION) | Some(PROTECTED) => {
+                self.lists.move_to_front(PROTECTED, idx);
+                if self.lists.len(PROTECTED) > self.protected_cap {
+                    let demoted = self.lists.back(PROTECTED).unwrap();
+                    self.lists.move_to_front(PROBATION, demoted);
+                }
+            }
+            _ => self.lists.move_to_front(WINDOW, idx),
+        }
+    }
+
+    fn on_miss(&mut self, hash: u64) {
+        self.sketch.increment(hash);
+    }
+
+    fn on_remove(&mut self, idx: usize) {
+        self.lists.remove(idx);
+    }
+
+    fn victim(&self) -> Option<usize> {
+        self.choose().map(|(victim, _)| victim)
+    }
+
+    fn evict(&mut self) -> Option<usize> {
+        let (victim, admitted) = self.choose()?;
+        self.lists.remove(victim);
+        if let Some(candidate) = admitted {
+            self.lists.move_to_front(PROBATION, candidate);
+        }
+        Some(victim)
+    }
+
+    fn order(&self) -> Box<dyn Iterator<Item = usize> + '_> {
+        Box::new(
+            self.lists
+                .iter(PROTECTED)
+                .chain(self.lists.iter(PROBATION))
+                .chain(self.lists.iter(WINDOW)),
+        )
+    }
+
+    fn clear(&mut self) {
+        self.lists.clear();
+        self.sketch.clear();
+    }
+}
@@ -0,0 +1,95 @@
+#![forbid(unsafe_code)]
+
+use crate::list::Lists;
+
+use super::{EvictionPolicy, Ghosts};
+
+const IN: usize = 0;
+const MAIN: usize = 1;
+
+// Full 2Q: new entries enter the FIFO `A1in`; keys evicted from it are
+// remembered in the ghost queue `A1out` and promoted to the LRU `Am` when
+// they come back.
+#[derive(Debug)]
+pub struct TwoQueue {
+    lists: Lists,
+    hashes: Vec<u64>,
+    out: Ghosts,
+    in_cap: usize,
+    out_cap: usize,
+}
+
+impl Default for TwoQueue {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TwoQueue {
+    pub fn new() -> Self {
+        Self {
+            lists: Lists::new(2),
+            hashes: Vec::new(),
+            out: Ghosts::default(),
+            in_cap: 1,
+            out_cap: 1,
+        }
+    }
+}
+
+impl EvictionPolicy for TwoQueue {
+    fn set_capacity(&mut self, capacity: usize) {
+        self.in_cap = (capacity / 4).max(1);
+        self.out_cap = (capacity / 2).max(1);
+        self.out.truncate(self.out_cap);
+    }
+
+    fn on_insert(&mut self, idx: usize, hash: u64) {
+        if idx >= self.hashes.len() {
+            self.hashes.resize(idx + 1, 0);
+        }
+        self.hashes[idx] = hash;
+
+        if self.out.remove(hash) {
+            self.lists.push_front(MAIN, idx);
+        } else {
+            self.lists.push_front(IN, idx);
+        }
+    }
+
+    fn on_access(&mut self, idx: usize, _hash: u64) {
+        if self.lists.list_of(idx) == Some(MAIN) {
+            self.lists.move_to_front(MAIN, idx);
+        }
+    }
+
+    fn on_remove(&mut self, idx: usize) {
+        self.lists.remove(idx);
+    }
+
+    fn victim(&self) -> Option<usize> {
+        if self.lists.len(IN) > self.in_cap || self.lists.len(MAIN) == 0 {
+            self.lists.back(IN)
+        } else {
+            self.lists.back(MAIN)
+        }
+    }
+
+    fn evict(&mut self) -> Option<usize> {
+        let idx = self.victim()?;
+        if self.lists.remove(idx) == Some(IN) {
+            self.out.push(self.hashes[idx]);
+            self.out.truncate(self.out_cap);
+        }
+        Some(idx)
+    }
+
+    fn order(&self) -> Box<dyn Iterator<Item = usize> + '_> {
+        Box::new(self.lists.iter(MAIN).chain(self.lists.iter(IN)))
+    }
+
+    fn clear(&mut self) {
+        self.lists.clear();
+        self.out.clear();
+    }
+}
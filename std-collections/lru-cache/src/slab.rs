@@ -0,0 +1,68 @@
+#![forbid(unsafe_code)]
+
+#[derive(Debug)]
+pub struct Slab<T> {
+    slots: Vec<Option<T>>,
+    free: Vec<usize>,
+    len: usize,
+}
+
+impl<T> Slab<T> {
+    pub fn new() -> Self {
+        Self {
+            slots: Vec::new(),
+            free: Vec::new(),
+            len: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn get(&self, idx: usize) -> &T {
+        self.slots[idx].as_ref().unwrap()
+    }
+
+    pub fn get_mut(&mut self, idx: usize) -> &mut T {
+        self.slots[idx].as_mut().unwrap()
+    }
+
+    pub fn insert(&mut self, value: T) -> usize {
+        self.len += 1;
+        match self.free.pop() {
+            Some(idx) => {
+                self.slots[idx] = Some(value);
+                idx
+            }
+            None => {
+                self.slots.push(Some(value));
+                self.slots.len() - 1
+            }
+        }
+    }
+
+    pub fn remove(&mut self, idx: usize) -> T {
+        let value = self.slots[idx].take().unwrap();
+        self.free.push(idx);
+        self.len -= 1;
+        value
+    }
+
+    pub fn clear(&mut self) {
+        self.slots.clear();
+        self.free.clear();
+        self.len = 0;
+    }
+
+    pub fn get_many_mut<I>(&mut self, order: I) -> Vec<&mut T>
+    where
+        I: IntoIterator<Item = usize>,
+    {
+        let mut slots: Vec<_> = self.slots.iter_mut().map(Option::as_mut).collect();
+        order
+            .into_iter()
+            .map(|idx| slots[idx].take().unwrap())
+            .collect()
+    }
+}
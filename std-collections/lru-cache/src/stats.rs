@@ -0,0 +1,71 @@
+#![forbid(unsafe_code)]
+
+use crate::EvictionReason;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    hits: u64,
+    misses: u64,
+    inserts: u64,
+    updates: u64,
+    evictions: [u64; 4],
+    peak_len: usize,
+}
+
+impl CacheStats {
+    pub fn hits(&self) -> u64 {
+        self.hits
+    }
+
+    pub fn misses(&self) -> u64 {
+        self.misses
+    }
+
+    pub fn hit_rate(&self) -> f64 {
+        match self.hits + self.misses {
+            0 => 0.0,
+            lookups => self.hits as f64 / lookups as f64,
+        }
+    }
+
+    pub fn inserts(&self) -> u64 {
+        self.inserts
+    }
+
+    pub fn updates(&self) -> u64 {
+        self.updates
+    }
+
+    pub fn evictions(&self, reason: EvictionReason) -> u64 {
+        self.evictions[reason as usize]
+    }
+
+    pub fn total_evictions(&self) -> u64 {
+        self.evictions.iter().sum()
+    }
+
+    pub fn peak_len(&self) -> usize {
+        self.peak_len
+    }
+
+    pub(crate) fn hit(&mut self) {
+        self.hits += 1;
+    }
+
+    pub(crate) fn miss(&mut self) {
+        self.misses += 1;
+    }
+
+    pub(crate) fn insert(&mut self, len: usize) {
+        self.inserts += 1;
+        self.peak_len = self.peak_len.max(len);
+    }
+
+    pub(crate) fn update(&mut self) {
+        self.updates += 1;
+    }
+
+    pub(crate) fn evict(&mut self, reason: EvictionReason) {
+        self.evictions[reason as usize] += 1;
+    }
+}
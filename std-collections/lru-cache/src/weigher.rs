@@ -0,0 +1,24 @@
+#![forbid(unsafe_code)]
+
+pub trait Weigher<K, V>: Send + Sync {
+    fn weigh(&self, key: &K, value: &V) -> usize;
+}
+
+impl<K, V, F> Weigher<K, V> for F
+where
+    F: Fn(&K, &V) -> usize + Send + Sync,
+{
+    fn weigh(&self, key: &K, value: &V) -> usize {
+        self(key, value)
+    }
+}
+
+// Every entry weighs one unit unless a weigher is set.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Unit;
+
+impl<K, V> Weigher<K, V> for Unit {
+    fn weigh(&self, _key: &K, _value: &V) -> usize {
+        1
+    }
+}
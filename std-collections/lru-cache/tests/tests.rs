@@ -1,6 +1,14 @@
-use lru_cache::LRUCache;
+use lru_cache::{
+    AdaptiveReplacement, Clock, ConcurrentLRUCache, EvictionPolicy, EvictionReason, LRUCache, Lfu,
+    Loaded, Lru, ManualClock, TwoQueue, WTinyLfu,
+};
 use ntest::timeout;
-use rand::Rng;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Barrier, Mutex};
+use std::thread;
+use std::time::Duration;
 
 struct NaiveLRUCache<K, V> {
     capacity: usize,
@@ -44,6 +52,46 @@ fn check_zero_capacity() {
     LRUCache::<i32, i32>::new(0);
 }
 
+#[test]
+fn check_send_and_sync() {
+    fn assert_sync<T: Send + Sync>() {}
+    assert_sync::<LRUCache<u32, u32>>();
+    assert_sync::<LRUCache<u32, u32, WTinyLfu>>();
+    assert_sync::<std::sync::RwLock<LRUCache<u32, u32>>>();
+    assert_sync::<ConcurrentLRUCache<u32, u32>>();
+}
+
+#[derive(Debug)]
+struct Forgetful;
+
+impl EvictionPolicy for Forgetful {
+    fn set_capacity(&mut self, _capacity: usize) {}
+
+    fn on_insert(&mut self, _idx: usize, _hash: u64) {}
+
+    fn on_access(&mut self, _idx: usize, _hash: u64) {}
+
+    fn on_remove(&mut self, _idx: usize) {}
+
+    fn victim(&self) -> Option<usize> {
+        None
+    }
+
+    fn order(&self) -> Box<dyn Iterator<Item = usize> + '_> {
+        Box::new(std::iter::empty())
+    }
+
+    fn clear(&mut self) {}
+}
+
+#[test]
+#[should_panic(expected = "no victim")]
+fn check_policy_without_victim() {
+    let mut cache = LRUCache::with_policy(1, Forgetful);
+    cache.insert(1, 1);
+    cache.insert(2, 2);
+}
+
 #[test]
 fn should_compile() {
     #[derive(Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
@@ -56,6 +104,29 @@ fn should_compile() {
     LRUCache::<Key, Value>::new(1);
 }
 
+#[test]
+fn key_without_clone_or_ord() {
+    #[derive(Hash, PartialEq, Eq, Debug)]
+    struct Key(String);
+
+    let mut cache = LRUCache::new(2);
+    assert_eq!(cache.insert(Key("a".to_string()), 1), None);
+    assert_eq!(cache.insert(Key("b".to_string()), 2), None);
+    assert_eq!(cache.get(&Key("a".to_string())), Some(&1));
+    assert_eq!(cache.insert(Key("c".to_string()), 3), None);
+    assert_eq!(cache.get(&Key("b".to_string())), None);
+    assert_eq!(cache.get(&Key("a".to_string())), Some(&1));
+    assert_eq!(cache.get(&Key("c".to_string())), Some(&3));
+}
+
+#[test]
+fn borrowed_lookup() {
+    let mut cache = LRUCache::new(2);
+    cache.insert("key".to_string(), 1);
+    assert_eq!(cache.get("key"), Some(&1));
+    assert_eq!(cache.get("missing"), None);
+}
+
 #[test]
 fn it_works1() {
     let mut cache = LRUCache::new(2);
@@ -154,3 +225,697 @@ fn stress() {
         }
     }
 }
+
+#[test]
+fn peek_and_remove() {
+    let mut cache = LRUCache::new(3);
+    assert!(cache.is_empty());
+    cache.insert(1, "a");
+    cache.insert(2, "b");
+    cache.insert(3, "c");
+    assert_eq!(cache.len(), 3);
+    assert_eq!(cache.capacity(), 3);
+
+    assert_eq!(cache.peek(&1), Some(&"a"));
+    assert_eq!(cache.peek_lru(), Some((&1, &"a")));
+    assert!(cache.contains_key(&2));
+    assert!(!cache.contains_key(&4));
+
+    *cache.get_mut(&1).unwrap() = "A";
+    assert_eq!(cache.peek_lru(), Some((&2, &"b")));
+    assert_eq!(cache.remove(&2), Some("b"));
+    assert_eq!(cache.remove(&2), None);
+    assert_eq!(cache.len(), 2);
+
+    cache.insert(4, "d");
+    cache.insert(5, "e");
+    assert_eq!(cache.pop_lru(), Some((1, "A")));
+    assert_eq!(cache.pop_lru(), Some((4, "d")));
+    assert_eq!(cache.pop_lru(), Some((5, "e")));
+    assert_eq!(cache.pop_lru(), None);
+    assert_eq!(cache.peek_lru(), None);
+}
+
+#[test]
+fn iteration_order() {
+    let mut cache = LRUCache::new(4);
+    for i in 0..6 {
+        cache.insert(i, i * 10);
+    }
+    cache.get(&3);
+
+    assert_eq!(cache.keys().copied().collect::<Vec<_>>(), [3, 5, 4, 2]);
+    assert_eq!(
+        cache.values().copied().collect::<Vec<_>>(),
+        [30, 50, 40, 20]
+    );
+    for (k, v) in cache.iter_mut() {
+        *v += k;
+    }
+    assert_eq!(
+        cache.iter().map(|(k, v)| (*k, *v)).collect::<Vec<_>>(),
+        [(3, 33), (5, 55), (4, 44), (2, 22)]
+    );
+}
+
+#[test]
+fn resize_and_clear() {
+    let mut cache = LRUCache::new(5);
+    for i in 0..5 {
+        cache.insert(i, i);
+    }
+
+    cache.resize(2);
+    assert_eq!(cache.capacity(), 2);
+    assert_eq!(cache.keys().copied().collect::<Vec<_>>(), [4, 3]);
+    cache.insert(5, 5);
+    assert_eq!(cache.keys().copied().collect::<Vec<_>>(), [5, 4]);
+
+    cache.resize(3);
+    cache.insert(6, 6);
+    assert_eq!(cache.len(), 3);
+
+    cache.clear();
+    assert!(cache.is_empty());
+    assert_eq!(cache.get(&5), None);
+    cache.insert(7, 7);
+    assert_eq!(cache.iter().count(), 1);
+}
+
+#[test]
+fn full_api_matches_naive() {
+    let mut cache = LRUCache::new(50);
+    let mut naive = NaiveLRUCache::new(50);
+    let mut rng = rand::thread_rng();
+    for _ in 0..100000 {
+        let key = rng.gen_range(0..150);
+        match rng.gen_range(0..5) {
+            0 => assert_eq!(cache.get(&key), naive.get(&key)),
+            1 => {
+                let expected = naive.cache.iter().position(|(k, _)| *k == key);
+                let expected = expected.map(|i| naive.cache.remove(i).1);
+                assert_eq!(cache.remove(&key), expected);
+            }
+            2 => assert_eq!(
+                cache.peek(&key),
+                naive.cache.iter().find(|(k, _)| *k == key).map(|(_, v)| v)
+            ),
+            _ => {
+                let value = rng.gen::<i8>();
+                assert_eq!(cache.insert(key, value), naive.insert(key, value));
+            }
+        }
+        assert_eq!(cache.len(), naive.cache.len());
+    }
+    assert!(cache.keys().eq(naive.cache.iter().rev().map(|(k, _)| k)));
+}
+
+#[test]
+fn lfu_evicts_least_frequent() {
+    let mut cache = LRUCache::with_policy(3, Lfu::new());
+    cache.insert(1, 1);
+    cache.insert(2, 2);
+    cache.insert(3, 3);
+    cache.get(&1);
+    cache.get(&1);
+    cache.get(&3);
+
+    assert_eq!(cache.peek_lru(), Some((&2, &2)));
+    cache.insert(4, 4);
+    assert!(!cache.contains_key(&2));
+    cache.insert(5, 5);
+    assert!(!cache.contains_key(&4));
+    assert_eq!(cache.keys().copied().collect::<Vec<_>>(), [1, 3, 5]);
+}
+
+#[test]
+fn scan_does_not_flush_hot_keys() {
+    fn survivors<P: EvictionPolicy>(policy: P) -> usize {
+        let mut cache = LRUCache::with_policy(100, policy);
+        let mut cold = 1000..;
+        for _ in 0..30 {
+            for i in 0..20 {
+                if cache.get(&i).is_none() {
+                    cache.insert(i, i);
+                }
+            }
+            for i in cold.by_ref().take(20) {
+                cache.insert(i, i);
+            }
+        }
+        for i in cold.take(200) {
+            cache.get(&i);
+            cache.insert(i, i);
+        }
+        (0..20).filter(|i| cache.contains_key(i)).count()
+    }
+
+    assert_eq!(survivors(Lru::new()), 0);
+    assert_eq!(survivors(Lfu::new()), 20);
+    assert_eq!(survivors(TwoQueue::new()), 20);
+    assert_eq!(survivors(AdaptiveReplacement::new()), 20);
+    assert_eq!(survivors(WTinyLfu::new()), 20);
+}
+
+fn check_policy<P: EvictionPolicy>(policy: P) {
+    let mut cache = LRUCache::with_policy(40, policy);
+    let mut rng = StdRng::seed_from_u64(7);
+    for step in 0..50000 {
+        let key = rng.gen_range(0..120);
+        match rng.gen_range(0..6) {
+            0 => {
+                cache.remove(&key);
+            }
+            1 => {
+                if let Some((key, _)) = cache.pop_lru() {
+                    assert!(!cache.contains_key(&key));
+                }
+            }
+            2 => {
+                cache.get(&key);
+            }
+            _ => {
+                cache.insert(key, step);
+                assert_eq!(cache.peek(&key), Some(&step));
+            }
+        }
+        if step % 10000 == 0 {
+            cache.resize(rng.gen_range(10..60));
+        }
+        assert!(cache.len() <= cache.capacity());
+        assert_eq!(cache.iter().count(), cache.len());
+    }
+
+    let victim = cache.peek_lru().map(|(&key, _)| key);
+    assert_eq!(cache.pop_lru().map(|(key, _)| key), victim);
+    cache.clear();
+    assert!(cache.is_empty());
+    assert_eq!(cache.peek_lru(), None);
+}
+
+#[test]
+fn policies_stay_consistent() {
+    check_policy(Lru::new());
+    check_policy(Lfu::new());
+    check_policy(TwoQueue::new());
+    check_policy(AdaptiveReplacement::new());
+    check_policy(WTinyLfu::new());
+}
+
+struct Zipf {
+    cdf: Vec<f64>,
+}
+
+impl Zipf {
+    fn new(n: usize, exponent: f64) -> Self {
+        let mut sum = 0.0;
+        let mut cdf: Vec<_> = (1..=n)
+            .map(|rank| {
+                sum += 1.0 / (rank as f64).powf(exponent);
+                sum
+            })
+            .collect();
+        cdf.iter_mut().for_each(|p| *p /= sum);
+        Self { cdf }
+    }
+
+    fn sample(&self, rng: &mut StdRng) -> u64 {
+        let p = rng.gen::<f64>();
+        self.cdf.partition_point(|&q| q < p) as u64
+    }
+}
+
+fn hit_rate<P: EvictionPolicy>(capacity: usize, policy: P, trace: &[u64]) -> f64 {
+    let mut cache = LRUCache::with_policy(capacity, policy);
+    let mut hits = 0;
+    for &key in trace {
+        if cache.get(&key).is_some() {
+            hits += 1;
+        } else {
+            cache.insert(key, ());
+        }
+    }
+    hits as f64 / trace.len() as f64
+}
+
+fn simulate(name: &str, capacity: usize, trace: &[u64]) -> [f64; 5] {
+    let rates = [
+        hit_rate(capacity, Lru::new(), trace),
+        hit_rate(capacity, Lfu::new(), trace),
+        hit_rate(capacity, TwoQueue::new(), trace),
+        hit_rate(capacity, AdaptiveReplacement::new(), trace),
+        hit_rate(capacity, WTinyLfu::new(), trace),
+    ];
+    println!(
+        "{name:>12}: lru {:.3}  lfu {:.3}  2q {:.3}  arc {:.3}  w-tinylfu {:.3}",
+        rates[0], rates[1], rates[2], rates[3], rates[4]
+    );
+    rates
+}
+
+#[test]
+fn simulate_zipf_traces() {
+    let mut rng = StdRng::seed_from_u64(42);
+    let zipf = Zipf::new(10000, 0.9);
+
+    let trace: Vec<_> = (0..200000).map(|_| zipf.sample(&mut rng)).collect();
+    let plain = simulate("zipf", 500, &trace);
+
+    let mut scan = 1_000_000;
+    let trace: Vec<_> = (0..200000)
+        .map(|i| {
+            if i % 1000 < 300 {
+                scan += 1;
+                scan
+            } else {
+                zipf.sample(&mut rng)
+            }
+        })
+        .collect();
+    let scanned = simulate("zipf + scan", 500, &trace);
+
+    for rate in plain.iter().chain(&scanned) {
+        assert!(0.0 < *rate && *rate < 1.0);
+    }
+    for rate in &scanned[1..] {
+        assert!(*rate > scanned[0]);
+    }
+    assert!(plain[4] > plain[0]);
+}
+
+#[test]
+fn ttl_expires_lazily() {
+    let clock = ManualClock::new();
+    let mut cache = LRUCache::new(4).with_clock(clock.clone());
+    cache.insert_with_ttl("a", 1, Duration::from_secs(10));
+    cache.insert_with_ttl("b", 2, Duration::from_secs(20));
+    cache.insert("c", 3);
+
+    clock.advance(Duration::from_secs(10));
+    assert_eq!(cache.peek(&"a"), None);
+    assert!(!cache.contains_key(&"a"));
+    assert_eq!(cache.len(), 3);
+    assert_eq!(cache.keys().copied().collect::<Vec<_>>(), ["c", "b"]);
+
+    assert_eq!(cache.get(&"a"), None);
+    assert_eq!(cache.len(), 2);
+    assert_eq!(cache.get(&"b"), Some(&2));
+
+    assert_eq!(
+        cache.insert_with_ttl("b", 4, Duration::from_secs(5)),
+        Some(2)
+    );
+    clock.advance(Duration::from_secs(15));
+    assert_eq!(cache.insert("b", 5), None);
+    assert_eq!(cache.get(&"b"), Some(&5));
+    assert_eq!(cache.get(&"c"), Some(&3));
+}
+
+#[test]
+fn default_ttl_and_purge() {
+    let clock = ManualClock::new();
+    let mut cache = LRUCache::new(10)
+        .with_clock(clock.clone())
+        .with_default_ttl(Duration::from_secs(60));
+    assert_eq!(cache.default_ttl(), Some(Duration::from_secs(60)));
+
+    for i in 0..5 {
+        cache.insert(i, i);
+    }
+    clock.advance(Duration::from_secs(30));
+    cache.insert(5, 5);
+    cache.insert_with_ttl(6, 6, Duration::from_secs(600));
+    cache.set_default_ttl(None);
+    cache.insert(7, 7);
+
+    clock.advance(Duration::from_secs(30));
+    assert_eq!(cache.purge_expired(), 5);
+    assert_eq!(cache.keys().copied().collect::<Vec<_>>(), [7, 6, 5]);
+    assert_eq!(cache.remove(&0), None);
+
+    clock.advance(Duration::from_secs(3600));
+    assert_eq!(cache.purge_expired(), 2);
+    assert_eq!(cache.get(&7), Some(&7));
+    assert_eq!(cache.len(), 1);
+}
+
+#[test]
+fn huge_ttl_never_expires() {
+    let clock = ManualClock::new();
+    let mut cache = LRUCache::new(2)
+        .with_clock(clock.clone())
+        .with_default_ttl(Duration::from_secs(u64::MAX));
+    cache.insert(1, 1);
+    cache.insert_with_ttl(2, 2, Duration::MAX);
+    assert_eq!(*cache.get_or_insert_with(1, || 10), 1);
+
+    let now = clock.now();
+    clock.advance(Duration::from_secs(3600));
+    clock.advance(Duration::MAX);
+    clock.advance(Duration::MAX);
+    assert!(clock.now() > now);
+    assert_eq!(cache.purge_expired(), 0);
+    assert_eq!(cache.get(&1), Some(&1));
+    assert_eq!(cache.get(&2), Some(&2));
+}
+
+#[test]
+fn lru_end_skips_expired() {
+    let clock = ManualClock::new();
+    let mut cache = LRUCache::new(4).with_clock(clock.clone()).with_stats();
+    cache.insert_with_ttl(1, 1, Duration::from_secs(10));
+    cache.insert_with_ttl(2, 2, Duration::from_secs(10));
+    cache.insert(3, 3);
+    cache.insert_with_ttl(4, 4, Duration::from_secs(10));
+    assert_eq!(cache.peek_lru(), Some((&1, &1)));
+
+    clock.advance(Duration::from_secs(10));
+    assert_eq!(cache.peek_lru(), Some((&3, &3)));
+    assert_eq!(cache.pop_lru(), Some((3, 3)));
+    assert_eq!(cache.stats().unwrap().evictions(EvictionReason::Expired), 2);
+    assert_eq!(cache.peek_lru(), None);
+    assert_eq!(cache.pop_lru(), None);
+    assert!(cache.is_empty());
+}
+
+#[test]
+fn weighted_capacity() {
+    let mut cache = LRUCache::new(100).with_max_weight(10, |_: &i32, v: &String| v.len());
+    assert_eq!(cache.max_weight(), 10);
+    cache.insert(1, "aaaa".to_string());
+    cache.insert(2, "bbb".to_string());
+    cache.insert(3, "cc".to_string());
+    assert_eq!(cache.total_weight(), 9);
+
+    cache.get(&1);
+    cache.insert(4, "ddd".to_string());
+    assert_eq!(cache.keys().copied().collect::<Vec<_>>(), [4, 1, 3]);
+    assert_eq!(cache.total_weight(), 9);
+
+    let heavy = "x".repeat(11);
+    assert_eq!(cache.try_insert(5, heavy.clone()), Err((5, heavy.clone())));
+    assert_eq!(cache.insert(1, heavy.clone()), Some("aaaa".to_string()));
+    assert_eq!(cache.get(&1), None);
+    assert_eq!(cache.insert(1, heavy.clone()), None);
+    assert_eq!(cache.len(), 2);
+    assert_eq!(cache.total_weight(), 5);
+
+    assert_eq!(
+        cache.insert(3, "cccccc".to_string()),
+        Some("cc".to_string())
+    );
+    assert_eq!(cache.keys().copied().collect::<Vec<_>>(), [3, 4]);
+    assert_eq!(cache.total_weight(), 9);
+
+    let replaced = Arc::new(Mutex::new(Vec::new()));
+    let log = replaced.clone();
+    let mut listened = LRUCache::new(10)
+        .with_max_weight(10, |_: &i32, v: &String| v.len())
+        .with_eviction_listener(move |k, v, reason| log.lock().unwrap().push((k, v, reason)));
+    listened.put(1, "a".to_string());
+    listened.put(1, heavy);
+    assert!(listened.is_empty());
+    assert_eq!(
+        *replaced.lock().unwrap(),
+        [(1, "a".to_string(), EvictionReason::Replaced)]
+    );
+
+    cache.remove(&3);
+    assert_eq!(cache.total_weight(), 3);
+    cache.clear();
+    assert_eq!(cache.total_weight(), 0);
+}
+
+#[test]
+fn weight_and_count_limits_together() {
+    let mut cache = LRUCache::new(3).with_max_weight(100, |k: &u32, _: &()| *k as usize);
+    for k in 1..=5 {
+        cache.insert(k, ());
+    }
+    assert_eq!(cache.keys().copied().collect::<Vec<_>>(), [5, 4, 3]);
+    cache.insert(92, ());
+    assert_eq!(cache.keys().copied().collect::<Vec<_>>(), [92, 5]);
+    assert_eq!(cache.total_weight(), 97);
+    assert_eq!(LRUCache::<u32, ()>::new(3).total_weight(), 0);
+}
+
+#[test]
+fn concurrent_basic() {
+    let cache = ConcurrentLRUCache::with_shards(8, 4);
+    assert_eq!(cache.shard_count(), 4);
+    assert_eq!(cache.capacity(), 8);
+    assert!(cache.is_empty());
+    assert_eq!(
+        ConcurrentLRUCache::<i32, i32>::with_shards(10, 4).capacity(),
+        10
+    );
+    assert_eq!(
+        ConcurrentLRUCache::<i32, i32>::with_shards(3, 8).shard_count(),
+        3
+    );
+
+    let value = Arc::new("shared".to_string());
+    assert_eq!(cache.insert("a", value.clone()), None);
+    assert_eq!(cache.get("a"), Some(value.clone()));
+    assert_eq!(Arc::strong_count(&value), 2);
+    assert!(cache.contains_key("a"));
+    assert_eq!(cache.remove("a"), Some(value));
+    assert_eq!(cache.peek("a"), None);
+
+    for i in 0..100 {
+        cache.insert("key", Arc::new(i.to_string()));
+    }
+    assert_eq!(cache.len(), 1);
+    cache.clear();
+    assert!(cache.is_empty());
+}
+
+#[test]
+#[timeout(60000)]
+fn concurrent_stress() {
+    let cache = ConcurrentLRUCache::with_shards(1000, 16);
+    thread::scope(|s| {
+        for seed in 0..8 {
+            let cache = &cache;
+            s.spawn(move || {
+                let mut rng = StdRng::seed_from_u64(seed);
+                for _ in 0..100000 {
+                    let key = rng.gen_range(0..5000u64);
+                    match rng.gen_range(0..4) {
+                        0 => {
+                            cache.insert(key, key * 3);
+                        }
+                        1 => {
+                            if let Some(value) = cache.remove(&key) {
+                                assert_eq!(value, key * 3);
+                            }
+                        }
+                        _ => {
+                            if let Some(value) = cache.get(&key) {
+                                assert_eq!(value, key * 3);
+                            }
+                        }
+                    }
+                }
+            });
+        }
+    });
+
+    assert!(cache.len() <= cache.capacity());
+    for key in 0..5000 {
+        if let Some(value) = cache.peek(&key) {
+            assert_eq!(value, key * 3);
+        }
+    }
+}
+
+#[test]
+fn eviction_listener() {
+    let evicted = Arc::new(Mutex::new(Vec::new()));
+    let log = evicted.clone();
+    let clock = ManualClock::new();
+    let mut cache = LRUCache::new(2)
+        .with_clock(clock.clone())
+        .with_eviction_listener(move |key: u32, value: String, reason| {
+            log.lock().unwrap().push((key, value, reason));
+        });
+    let take = || std::mem::take(&mut *evicted.lock().unwrap());
+
+    cache.insert(1, "a".to_string());
+    cache.insert(2, "b".to_string());
+    cache.insert(3, "c".to_string());
+    assert_eq!(take(), [(1, "a".to_string(), EvictionReason::Capacity)]);
+
+    assert_eq!(cache.insert(2, "bb".to_string()), Some("b".to_string()));
+    cache.put(2, "bbb".to_string());
+    assert_eq!(take(), [(2, "bb".to_string(), EvictionReason::Replaced)]);
+
+    assert_eq!(cache.remove(&3), Some("c".to_string()));
+    assert!(cache.invalidate(&2));
+    assert!(!cache.invalidate(&2));
+    assert_eq!(take(), [(2, "bbb".to_string(), EvictionReason::Explicit)]);
+
+    cache.insert_with_ttl(4, "d".to_string(), Duration::from_secs(1));
+    cache.insert(5, "e".to_string());
+    clock.advance(Duration::from_secs(1));
+    assert_eq!(cache.get(&4), None);
+    assert_eq!(take(), [(4, "d".to_string(), EvictionReason::Expired)]);
+
+    cache.insert(6, "f".to_string());
+    cache.resize(1);
+    cache.clear();
+    assert_eq!(
+        take(),
+        [
+            (5, "e".to_string(), EvictionReason::Capacity),
+            (6, "f".to_string(), EvictionReason::Explicit),
+        ]
+    );
+    assert!(cache.is_empty());
+}
+
+#[test]
+fn get_or_insert_with() {
+    let mut cache = LRUCache::new(2);
+    assert_eq!(*cache.get_or_insert_with("a", || 1), 1);
+    assert_eq!(*cache.get_or_insert_with("a", || unreachable!()), 1);
+
+    assert_eq!(
+        cache.try_get_or_insert_with("b", || Err("offline")),
+        Err("offline")
+    );
+    assert!(!cache.contains_key("b"));
+    assert_eq!(
+        cache.try_get_or_insert_with("b", || Ok::<_, ()>(2)),
+        Ok(Loaded::Cached(&2))
+    );
+
+    cache.get_or_insert_with("c", || 3);
+    assert_eq!(cache.keys().copied().collect::<Vec<_>>(), ["c", "b"]);
+
+    let mut cache = LRUCache::new(2).with_max_weight(3, |_: &&str, v: &String| v.len());
+    let heavy = "x".repeat(4);
+    assert_eq!(
+        cache.get_or_insert_with("a", || heavy.clone()),
+        Loaded::Uncached(heavy.clone())
+    );
+    assert!(cache.is_empty());
+    assert_eq!(
+        cache.try_get_or_insert_with("b", || Ok::<_, ()>("bb".to_string())),
+        Ok(Loaded::Cached(&"bb".to_string()))
+    );
+}
+
+#[test]
+#[timeout(60000)]
+fn concurrent_single_flight() {
+    let cache = ConcurrentLRUCache::with_shards(16, 4);
+    let loads = AtomicUsize::new(0);
+    let barrier = Barrier::new(8);
+    thread::scope(|s| {
+        for _ in 0..8 {
+            s.spawn(|| {
+                barrier.wait();
+                let value = cache.get_or_insert_with(7, || {
+                    loads.fetch_add(1, Ordering::SeqCst);
+                    thread::sleep(Duration::from_millis(100));
+                    "seven".to_string()
+                });
+                assert_eq!(value, "seven");
+            });
+        }
+    });
+    assert_eq!(loads.load(Ordering::SeqCst), 1);
+
+    let attempts = AtomicUsize::new(0);
+    thread::scope(|s| {
+        for _ in 0..4 {
+            s.spawn(|| {
+                load_eight(&cache, &attempts);
+            });
+        }
+    });
+    assert_eq!(cache.get(&8), Some("eight".to_string()));
+    assert!(attempts.load(Ordering::SeqCst) >= 2);
+}
+
+fn load_eight(cache: &ConcurrentLRUCache<i32, String>, attempts: &AtomicUsize) {
+    let result = cache.try_get_or_insert_with(8, || {
+        thread::sleep(Duration::from_millis(20));
+        match attempts.fetch_add(1, Ordering::SeqCst) {
+            0 => Err(()),
+            _ => Ok("eight".to_string()),
+        }
+    });
+    if let Ok(value) = result {
+        assert_eq!(value, "eight");
+    }
+}
+
+#[test]
+fn stats() {
+    let clock = ManualClock::new();
+    let mut cache = LRUCache::new(2).with_clock(clock.clone()).with_stats();
+    cache.insert(1, 1);
+    cache.insert(2, 2);
+    cache.insert(2, 20);
+    cache.get(&1);
+    cache.get(&3);
+    cache.insert(3, 3);
+    cache.insert_with_ttl(4, 4, Duration::from_secs(1));
+    clock.advance(Duration::from_secs(1));
+    cache.get(&4);
+    cache.put(3, 30);
+    cache.invalidate(&3);
+    cache.get_or_insert_with(5, || 5);
+
+    let stats = *cache.stats().unwrap();
+    assert_eq!(stats.hits(), 1);
+    assert_eq!(stats.misses(), 3);
+    assert_eq!(stats.hit_rate(), 0.25);
+    assert_eq!(stats.inserts(), 5);
+    assert_eq!(stats.updates(), 2);
+    assert_eq!(stats.evictions(EvictionReason::Capacity), 2);
+    assert_eq!(stats.evictions(EvictionReason::Expired), 1);
+    assert_eq!(stats.evictions(EvictionReason::Replaced), 1);
+    assert_eq!(stats.evictions(EvictionReason::Explicit), 1);
+    assert_eq!(stats.total_evictions(), 5);
+    assert_eq!(stats.peak_len(), 2);
+
+    cache.reset_stats();
+    assert_eq!(cache.stats().unwrap().hits(), 0);
+    cache.insert(6, 6);
+    cache.clear();
+    assert_eq!(
+        cache.stats().unwrap().evictions(EvictionReason::Explicit),
+        2
+    );
+    assert_eq!(LRUCache::<i32, i32>::new(1).stats(), None);
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn save_and_load() {
+    let mut cache = LRUCache::new(4);
+    for i in 0..4 {
+        cache.insert(i, i.to_string());
+    }
+    cache.get(&1);
+
+    let mut saved = Vec::new();
+    cache.save(&mut saved).unwrap();
+
+    let mut loaded = LRUCache::<i32, String>::load(saved.as_slice(), 4).unwrap();
+    assert!(loaded.iter().eq(cache.iter()));
+    loaded.insert(4, "4".to_string());
+    assert_eq!(loaded.keys().copied().collect::<Vec<_>>(), [4, 1, 3, 2]);
+
+    let mut smaller = LRUCache::<i32, String>::load(saved.as_slice(), 2).unwrap();
+    assert_eq!(smaller.capacity(), 2);
+    assert_eq!(smaller.keys().copied().collect::<Vec<_>>(), [1, 3]);
+    smaller.insert(5, "5".to_string());
+    assert_eq!(smaller.keys().copied().collect::<Vec<_>>(), [5, 1]);
+
+    assert!(LRUCache::<i32, String>::load(&b"[[1, 2]]"[..], 2).is_err());
+    assert!(LRUCache::<i32, String>::load(&b"[] []"[..], 2).is_err());
+}